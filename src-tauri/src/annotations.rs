@@ -0,0 +1,192 @@
+use std::collections::{BTreeSet, HashMap};
+
+use anyhow::bail;
+use chrono::{DateTime, Utc};
+use indoc::indoc;
+use rusqlite::{Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+use crate::commands::error::CommandError;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct MatchNote {
+    pub id: i64,
+    pub match_id: String,
+    pub author: Option<String>,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl MatchNote {
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get(0)?,
+            match_id: row.get(1)?,
+            author: row.get(2)?,
+            body: row.get(3)?,
+            created_at: row.get(4)?,
+            updated_at: row.get(5)?,
+        })
+    }
+}
+
+const NOTE_COLUMNS: &str = "id, match_id, author, body, created_at, updated_at";
+
+/// Tags are matched exactly, so surrounding whitespace from the input box is dropped.
+fn normalize_tag(tag: &str) -> anyhow::Result<&str> {
+    let tag = tag.trim();
+    if tag.is_empty() {
+        bail!("tags can't be empty");
+    }
+    Ok(tag)
+}
+
+fn ensure_match_exists(conn: &Connection, match_id: &str) -> anyhow::Result<()> {
+    conn.query_row(
+        "SELECT 1 FROM matches WHERE id = ?1",
+        [match_id],
+        |_| Ok(()),
+    )
+    .optional()?
+    .ok_or_else(|| CommandError::not_found(format!("match {match_id}")))?;
+    Ok(())
+}
+
+fn get_note(conn: &Connection, note_id: i64) -> anyhow::Result<MatchNote> {
+    Ok(conn
+        .query_row(
+            &format!("SELECT {NOTE_COLUMNS} FROM match_notes WHERE id = ?1"),
+            [note_id],
+            MatchNote::from_row,
+        )
+        .optional()?
+        .ok_or_else(|| CommandError::not_found(format!("note {note_id}")))?)
+}
+
+/// Notes on a match, oldest first.
+pub(crate) fn notes(conn: &Connection, match_id: &str) -> rusqlite::Result<Vec<MatchNote>> {
+    let mut statement = conn.prepare(&format!(
+        "SELECT {NOTE_COLUMNS} FROM match_notes WHERE match_id = ?1 ORDER BY created_at, id"
+    ))?;
+    let rows = statement.query_map([match_id], MatchNote::from_row)?;
+    rows.collect()
+}
+
+pub(crate) fn add_note(
+    conn: &Connection,
+    match_id: &str,
+    author: Option<&str>,
+    body: &str,
+) -> anyhow::Result<MatchNote> {
+    ensure_match_exists(conn, match_id)?;
+    let now = Utc::now();
+    conn.execute(
+        indoc! {r"
+            INSERT INTO match_notes (match_id, author, body, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?4)
+        "},
+        (match_id, author, body, now),
+    )?;
+    get_note(conn, conn.last_insert_rowid())
+}
+
+pub(crate) fn edit_note(conn: &Connection, note_id: i64, body: &str) -> anyhow::Result<MatchNote> {
+    let updated = conn.execute(
+        "UPDATE match_notes SET body = ?2, updated_at = ?3 WHERE id = ?1",
+        (note_id, body, Utc::now()),
+    )?;
+    if updated == 0 {
+        return Err(CommandError::not_found(format!("note {note_id}")).into());
+    }
+    get_note(conn, note_id)
+}
+
+/// Deletes a note, returning the id of the match it was on.
+pub(crate) fn remove_note(conn: &Connection, note_id: i64) -> anyhow::Result<String> {
+    Ok(conn
+        .query_row(
+            "DELETE FROM match_notes WHERE id = ?1 RETURNING match_id",
+            [note_id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .ok_or_else(|| CommandError::not_found(format!("note {note_id}")))?)
+}
+
+/// Copies the notes of `from_match_id` onto `to_match_id` as new notes.
+pub(crate) fn copy_notes(
+    conn: &Connection,
+    from_match_id: &str,
+    to_match_id: &str,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        indoc! {r"
+            INSERT INTO match_notes (match_id, author, body, created_at, updated_at)
+            SELECT ?2, author, body, created_at, updated_at FROM match_notes WHERE match_id = ?1
+            ORDER BY id
+        "},
+        (from_match_id, to_match_id),
+    )
+}
+
+/// A match's tags in alphabetical order.
+pub(crate) fn tags(conn: &Connection, match_id: &str) -> rusqlite::Result<BTreeSet<String>> {
+    let mut statement = conn.prepare("SELECT tag FROM match_tags WHERE match_id = ?1")?;
+    let rows = statement.query_map([match_id], |row| row.get(0))?;
+    rows.collect()
+}
+
+/// Every tagged match's tags, for filtering many matches with one query.
+pub(crate) fn tags_by_match(
+    conn: &Connection,
+) -> rusqlite::Result<HashMap<String, BTreeSet<String>>> {
+    let mut statement = conn.prepare("SELECT match_id, tag FROM match_tags")?;
+    let rows = statement.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get(1)?)))?;
+
+    let mut tags: HashMap<String, BTreeSet<String>> = HashMap::new();
+    for row in rows {
+        let (match_id, tag) = row?;
+        tags.entry(match_id).or_default().insert(tag);
+    }
+    Ok(tags)
+}
+
+/// Every tag in use and how many matches carry it, most used first.
+pub(crate) fn tag_counts(conn: &Connection) -> rusqlite::Result<Vec<(String, u32)>> {
+    let mut statement = conn.prepare(indoc! {r"
+        SELECT tag, COUNT(*) FROM match_tags
+        GROUP BY tag
+        ORDER BY COUNT(*) DESC, tag
+    "})?;
+    let rows = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    rows.collect()
+}
+
+/// Tags a match, returning its tags. Adding a tag it already has changes nothing.
+pub(crate) fn add_tag(
+    conn: &Connection,
+    match_id: &str,
+    tag: &str,
+) -> anyhow::Result<BTreeSet<String>> {
+    ensure_match_exists(conn, match_id)?;
+    conn.execute(
+        "INSERT OR IGNORE INTO match_tags (match_id, tag, created_at) VALUES (?1, ?2, ?3)",
+        (match_id, normalize_tag(tag)?, Utc::now()),
+    )?;
+    Ok(tags(conn, match_id)?)
+}
+
+/// Untags a match, returning its remaining tags.
+pub(crate) fn remove_tag(
+    conn: &Connection,
+    match_id: &str,
+    tag: &str,
+) -> anyhow::Result<BTreeSet<String>> {
+    ensure_match_exists(conn, match_id)?;
+    conn.execute(
+        "DELETE FROM match_tags WHERE match_id = ?1 AND tag = ?2",
+        (match_id, normalize_tag(tag)?),
+    )?;
+    Ok(tags(conn, match_id)?)
+}
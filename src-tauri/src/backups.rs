@@ -0,0 +1,208 @@
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use rusqlite::backup::Progress;
+use rusqlite::{Connection, DatabaseName, OpenFlags};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use crate::commands::error::CommandError;
+use crate::database::DatabaseState;
+use crate::schema;
+
+/// Oldest backups beyond this many of one reason are deleted after each new one, so scheduled
+/// backups never push out the ones taken before a migration or restore.
+const MAX_BACKUPS: usize = 10;
+
+const BACKUP_INTERVAL: chrono::Duration = chrono::Duration::hours(12);
+
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+const TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%S";
+
+pub(crate) fn backups_dir(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join("backups")
+}
+
+/// Why a backup was taken, the last part of its file name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum BackupReason {
+    Scheduled,
+    Manual,
+    PreMigration,
+    PreRestore,
+}
+
+impl BackupReason {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Scheduled => "scheduled",
+            Self::Manual => "manual",
+            Self::PreMigration => "pre_migration",
+            Self::PreRestore => "pre_restore",
+        }
+    }
+
+    fn parse(reason: &str) -> Option<Self> {
+        match reason {
+            "scheduled" => Some(Self::Scheduled),
+            "manual" => Some(Self::Manual),
+            "pre_migration" => Some(Self::PreMigration),
+            "pre_restore" => Some(Self::PreRestore),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct BackupInfo {
+    pub file_name: String,
+    pub created_at: DateTime<Utc>,
+    pub reason: BackupReason,
+    pub size_bytes: u64,
+}
+
+impl BackupInfo {
+    /// `matches-20240901T120000-scheduled.db`, or `...-scheduled-2.db` for the second taken
+    /// within the same second.
+    fn from_path(path: &Path) -> Option<Self> {
+        let file_name = path.file_name()?.to_str()?.to_string();
+        let stem = file_name.strip_prefix("matches-")?.strip_suffix(".db")?;
+        let (timestamp, reason) = stem.split_once('-')?;
+        let reason = reason.split_once('-').map_or(reason, |(reason, _)| reason);
+        Some(Self {
+            created_at: NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT)
+                .ok()?
+                .and_utc(),
+            reason: BackupReason::parse(reason)?,
+            size_bytes: fs::metadata(path).ok()?.len(),
+            file_name,
+        })
+    }
+}
+
+/// Newest first.
+pub(crate) fn list(dir: &Path) -> anyhow::Result<Vec<BackupInfo>> {
+    if !dir.exists() {
+        return Ok(Vec::default());
+    }
+    let mut backups = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        if let Some(backup) = BackupInfo::from_path(&entry?.path()) {
+            backups.push(backup);
+        }
+    }
+    // a numbered file name was taken after the plain one of the same second
+    backups.sort_by_key(|backup| {
+        Reverse((
+            backup.created_at,
+            backup.file_name.len(),
+            backup.file_name.clone(),
+        ))
+    });
+    Ok(backups)
+}
+
+fn check_integrity(path: &Path) -> anyhow::Result<()> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let result: String = conn.query_row("PRAGMA integrity_check", [], |row| row.get(0))?;
+    if result != "ok" {
+        bail!("{} failed its integrity check: {}", path.display(), result);
+    }
+    Ok(())
+}
+
+fn rotate(dir: &Path, reason: BackupReason) -> anyhow::Result<()> {
+    let backups = list(dir)?
+        .into_iter()
+        .filter(|backup| backup.reason == reason);
+    for backup in backups.skip(MAX_BACKUPS) {
+        info!("Removing old backup {}", backup.file_name);
+        fs::remove_file(dir.join(backup.file_name))?;
+    }
+    Ok(())
+}
+
+/// Copies the live database page by page with `SQLite`'s online backup API, so it's consistent
+/// even though ingest may have written moments before.
+pub(crate) fn create(
+    conn: &Connection,
+    dir: &Path,
+    reason: BackupReason,
+) -> anyhow::Result<BackupInfo> {
+    fs::create_dir_all(dir)?;
+    let stem = format!(
+        "matches-{}-{}",
+        Utc::now().format(TIMESTAMP_FORMAT),
+        reason.as_str()
+    );
+    let mut file_name = format!("{stem}.db");
+    for n in 2.. {
+        if !dir.join(&file_name).exists() {
+            break;
+        }
+        file_name = format!("{stem}-{n}.db");
+    }
+    let path = dir.join(&file_name);
+    conn.backup(DatabaseName::Main, &path, None)?;
+    if let Err(e) = check_integrity(&path) {
+        fs::remove_file(&path)?;
+        return Err(e);
+    }
+    info!("Created backup {}", file_name);
+    rotate(dir, reason)?;
+    BackupInfo::from_path(&path).with_context(|| format!("Could not read back {file_name}"))
+}
+
+/// Replaces the live database with a backup from `dir`, after checking the backup is intact and
+/// backing up the current state so the restore itself can be undone.
+pub(crate) fn restore(
+    conn: &mut Connection,
+    dir: &Path,
+    file_name: &str,
+) -> anyhow::Result<BackupInfo> {
+    let path = dir.join(file_name);
+    if path.parent() != Some(dir) || BackupInfo::from_path(&path).is_none() {
+        return Err(CommandError::not_found(format!("backup {file_name}")).into());
+    }
+    check_integrity(&path)?;
+    let safety_backup = create(conn, dir, BackupReason::PreRestore)?;
+    conn.restore(DatabaseName::Main, &path, None::<fn(Progress)>)?;
+    // The backup may predate migrations this build knows about.
+    schema::migrate(conn)?;
+    info!("Restored backup {}", file_name);
+    Ok(safety_backup)
+}
+
+fn backup_due(dir: &Path) -> anyhow::Result<bool> {
+    Ok(list(dir)?
+        .iter()
+        .find(|backup| backup.reason == BackupReason::Scheduled)
+        .map_or(true, |latest| {
+            Utc::now() - latest.created_at >= BACKUP_INTERVAL
+        }))
+}
+
+pub(crate) fn start_scheduled_backups(db: DatabaseState, dir: PathBuf) {
+    std::thread::spawn(move || loop {
+        match backup_due(&dir) {
+            Ok(true) => {
+                let backup = db
+                    .read()
+                    .map_err(anyhow::Error::from)
+                    .and_then(|db| create(&db.conn, &dir, BackupReason::Scheduled));
+                if let Err(e) = backup {
+                    error!("Error creating scheduled backup: {}", e);
+                }
+            }
+            Ok(false) => {}
+            Err(e) => error!("Error listing backups: {}", e),
+        }
+        std::thread::sleep(SCHEDULE_CHECK_INTERVAL);
+    });
+}
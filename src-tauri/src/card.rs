@@ -1,9 +1,14 @@
 use std::{cmp::Ordering, fmt::Display};
 
 use ap_core::cards::CardDbEntry;
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+use crate::card_metadata::CardMetadata;
+
+/// Declared in the order a card with several types is filed under, so an artifact creature is
+/// primarily a creature.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum CardType {
     Creature,
     Land,
@@ -13,6 +18,7 @@ pub enum CardType {
     Instant,
     Sorcery,
     Battle,
+    Kindred,
     #[default]
     Unknown,
 }
@@ -25,13 +31,156 @@ impl Display for CardType {
     }
 }
 
+/// `Legendary Artifact Creature — Human Wizard` split into its three parts.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TypeLine {
+    pub supertypes: Vec<String>,
+    /// Sorted with the primary type first, never empty.
+    pub types: Vec<CardType>,
+    pub subtypes: Vec<String>,
+}
+
+const SUPERTYPES: [&str; 7] = [
+    "Basic",
+    "Legendary",
+    "Ongoing",
+    "Snow",
+    "World",
+    "Elite",
+    "Host",
+];
+
+impl TypeLine {
+    pub fn parse(type_line: &str) -> Self {
+        let (types, subtypes) = type_line
+            .split_once('\u{2014}')
+            .or_else(|| type_line.split_once(" - "))
+            .unwrap_or((type_line, ""));
+
+        let mut parsed = Self {
+            subtypes: subtypes.split_whitespace().map(str::to_string).collect(),
+            ..Self::default()
+        };
+        for word in types.split_whitespace() {
+            if SUPERTYPES.contains(&word) {
+                parsed.supertypes.push(word.to_string());
+                continue;
+            }
+            let card_type = match word {
+                "Creature" => CardType::Creature,
+                "Land" => CardType::Land,
+                "Artifact" => CardType::Artifact,
+                "Enchantment" => CardType::Enchantment,
+                "Planeswalker" => CardType::Planeswalker,
+                "Instant" => CardType::Instant,
+                "Sorcery" => CardType::Sorcery,
+                "Battle" => CardType::Battle,
+                "Kindred" | "Tribal" => CardType::Kindred,
+                _ => continue,
+            };
+            parsed.types.push(card_type);
+        }
+        if parsed.types.is_empty() {
+            parsed.types.push(CardType::Unknown);
+        }
+        parsed.types.sort();
+        parsed.types.dedup();
+        parsed
+    }
+
+    pub fn primary_type(&self) -> CardType {
+        self.types.first().cloned().unwrap_or_default()
+    }
+
+    pub fn has_supertype(&self, supertype: &str) -> bool {
+        self.supertypes.iter().any(|s| s == supertype)
+    }
+}
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub enum Rarity {
+    #[default]
+    Unknown,
+    Common,
+    Uncommon,
+    Rare,
+    Mythic,
+}
+
+impl Rarity {
+    /// Scryfall spells rarities in lowercase, `special` and `bonus` cards can't be crafted.
+    pub fn from_scryfall(rarity: &str) -> Self {
+        match rarity {
+            "common" => Self::Common,
+            "uncommon" => Self::Uncommon,
+            "rare" => Self::Rare,
+            "mythic" => Self::Mythic,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl Display for Rarity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        serde_json::to_string(self)
+            .unwrap_or("Unknown".to_string())
+            .fmt(f)
+    }
+}
+
+/// `{2}{W/U}{W/U}` into `["2", "W/U", "W/U"]`.
+pub fn mana_symbols(mana_cost: &str) -> Vec<String> {
+    mana_cost
+        .split('}')
+        .filter_map(|symbol| symbol.strip_prefix('{'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// One face of a double-faced, split or adventure card.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct CardFace {
+    pub name: String,
+    pub type_line: TypeLine,
+    pub mana_cost: Vec<String>,
+    pub colors: Vec<String>,
+    pub oracle_text: String,
+    pub image_uri: String,
+}
+
+impl From<&ap_core::cards::CardFace> for CardFace {
+    fn from(face: &ap_core::cards::CardFace) -> Self {
+        Self {
+            name: face.name.clone(),
+            type_line: TypeLine::parse(&face.type_line),
+            mana_cost: mana_symbols(face.mana_cost.as_deref().unwrap_or_default()),
+            colors: face.colors.clone().unwrap_or_default(),
+            oracle_text: face.oracle_text.clone().unwrap_or_default(),
+            image_uri: face.image_uri.clone().unwrap_or_default(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct Card {
     pub name: String,
-    pub type_field: CardType,
+    pub type_line: TypeLine,
     pub mana_value: i16,
     pub quantity: u16,
     pub image_uri: String,
+    pub rarity: Rarity,
+    pub mana_cost: Vec<String>,
+    pub colors: Vec<String>,
+    pub color_identity: Vec<String>,
+    pub set: String,
+    pub collector_number: String,
+    pub oracle_text: String,
+    /// Scryfall's layout, `normal`, `transform`, `modal_dfc`, `split`, `adventure`...
+    pub layout: String,
+    /// Empty for single-faced cards.
+    pub faces: Vec<CardFace>,
 }
 
 impl Card {
@@ -41,16 +190,113 @@ impl Card {
             ..Default::default()
         }
     }
+
+    /// Lands plus modal double-faced cards that can be played as one, which decks run in place
+    /// of lands. Transforming cards only become lands once on the battlefield.
+    pub fn is_land(&self) -> bool {
+        self.type_line.types.contains(&CardType::Land)
+            || (self.layout == "modal_dfc"
+                && self
+                    .faces
+                    .iter()
+                    .any(|face| face.type_line.types.contains(&CardType::Land)))
+    }
+
+    /// Types of every face, so an adventure creature is also an instant or sorcery.
+    pub fn all_types(&self) -> Vec<CardType> {
+        if self.faces.is_empty() {
+            return self.type_line.types.clone();
+        }
+        self.faces
+            .iter()
+            .flat_map(|face| face.type_line.types.iter())
+            .cloned()
+            .sorted()
+            .dedup()
+            .collect()
+    }
+
+    /// The card `ap_core` knows, with the rarity, collector number and oracle text cards-full.json
+    /// adds when we have its metadata.
+    pub fn from_entry(entry: &CardDbEntry, metadata: Option<&CardMetadata>) -> Self {
+        let faces: Vec<CardFace> = entry
+            .card_faces
+            .iter()
+            .flatten()
+            .map(CardFace::from)
+            .collect();
+
+        // Scryfall leaves the card level fields empty when they differ between faces, its
+        // name is the full `A // B` though.
+        let (type_line, mana_cost, oracle_text) = match faces.first() {
+            Some(front_face) => (
+                front_face.type_line.clone(),
+                front_face.mana_cost.clone(),
+                front_face.oracle_text.clone(),
+            ),
+            None => (
+                TypeLine::parse(&entry.type_line),
+                mana_symbols(&entry.mana_cost),
+                metadata
+                    .map(|metadata| metadata.oracle_text.clone())
+                    .unwrap_or_default(),
+            ),
+        };
+        let image_uri = entry.image_uri.clone().unwrap_or_else(|| {
+            faces
+                .first()
+                .map(|face| face.image_uri.clone())
+                .unwrap_or_default()
+        });
+        let colors = entry.colors.clone().unwrap_or_else(|| {
+            faces
+                .iter()
+                .flat_map(|face| face.colors.iter().cloned())
+                .unique()
+                .collect()
+        });
+
+        Self {
+            name: entry.name.clone(),
+            type_line,
+            #[allow(clippy::cast_possible_truncation)]
+            mana_value: entry.cmc as i16,
+            quantity: 1,
+            image_uri,
+            rarity: metadata.map_or(Rarity::Unknown, |metadata| {
+                Rarity::from_scryfall(&metadata.rarity)
+            }),
+            mana_cost,
+            colors,
+            color_identity: entry.color_identity.clone(),
+            set: entry.set.clone(),
+            collector_number: metadata
+                .map(|metadata| metadata.collector_number.clone())
+                .unwrap_or_default(),
+            oracle_text,
+            layout: entry.layout.clone(),
+            faces,
+        }
+    }
 }
 
 impl Default for Card {
     fn default() -> Self {
         Self {
             name: "Unknown".to_string(),
-            type_field: CardType::Unknown,
+            type_line: TypeLine::parse(""),
             mana_value: 0,
             quantity: 0,
             image_uri: String::new(),
+            rarity: Rarity::Unknown,
+            mana_cost: Vec::new(),
+            colors: Vec::new(),
+            color_identity: Vec::new(),
+            set: String::new(),
+            collector_number: String::new(),
+            oracle_text: String::new(),
+            layout: String::new(),
+            faces: Vec::new(),
         }
     }
 }
@@ -78,58 +324,3 @@ impl PartialOrd<Self> for Card {
         Some(self.cmp(other))
     }
 }
-
-impl From<&CardDbEntry> for Card {
-    fn from(entry: &CardDbEntry) -> Self {
-        let (name, type_line) = if let Some(card_faces) = &entry.card_faces {
-            let front_face = &card_faces[0];
-            (front_face.name.clone(), front_face.type_line.clone())
-        } else {
-            (entry.name.clone(), entry.type_line.clone())
-        };
-        let image_uri = if let Some(image_uri) = &entry.image_uri {
-            image_uri.clone()
-        } else {
-            // Gross!
-            entry
-                .card_faces
-                .as_ref()
-                .and_then(|faces| faces.first().map(|face| face.image_uri.clone()))
-                .flatten()
-                .as_ref()
-                .unwrap_or(&String::new())
-                .to_string()
-        };
-
-        Self {
-            name,
-            type_field: card_type_from_type_line(&type_line),
-            #[allow(clippy::cast_possible_truncation)]
-            mana_value: entry.cmc as i16,
-            quantity: 1,
-            image_uri: image_uri.clone(),
-        }
-    }
-}
-
-pub fn card_type_from_type_line(type_line: &str) -> CardType {
-    if type_line.contains("Creature") {
-        CardType::Creature
-    } else if type_line.contains("Land") {
-        CardType::Land
-    } else if type_line.contains("Artifact") {
-        CardType::Artifact
-    } else if type_line.contains("Enchantment") {
-        CardType::Enchantment
-    } else if type_line.contains("Planeswalker") {
-        CardType::Planeswalker
-    } else if type_line.contains("Instant") {
-        CardType::Instant
-    } else if type_line.contains("Sorcery") {
-        CardType::Sorcery
-    } else if type_line.contains("Battle") {
-        CardType::Battle
-    } else {
-        CardType::Unknown
-    }
-}
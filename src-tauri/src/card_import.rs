@@ -0,0 +1,306 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+use ap_core::cards::CardDbEntry;
+use indoc::indoc;
+use rusqlite::{Connection, OpenFlags};
+use serde::de::{SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::card::mana_symbols;
+use crate::card_metadata::CardMetadata;
+use crate::cards::Cards;
+
+/// Cards imported by the user, they take precedence over the bundled cards-full.json.
+pub(crate) fn overlay_path(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join("cards-overlay.json")
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct OverlayFace {
+    pub name: String,
+    pub type_line: String,
+    pub mana_cost: Option<String>,
+    pub image_uri: Option<String>,
+    pub colors: Option<Vec<String>>,
+    pub oracle_text: Option<String>,
+}
+
+/// Shaped like a cards-full.json entry so it reads back as a `CardDbEntry` plus `CardMetadata`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct OverlayCard {
+    pub id: i32,
+    pub set: String,
+    pub name: String,
+    pub lang: String,
+    pub image_uri: Option<String>,
+    pub mana_cost: String,
+    pub cmc: f32,
+    pub type_line: String,
+    pub layout: String,
+    pub colors: Option<Vec<String>>,
+    pub color_identity: Vec<String>,
+    pub card_faces: Option<Vec<OverlayFace>>,
+    pub rarity: String,
+    pub collector_number: String,
+    pub oracle_text: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ScryfallImages {
+    normal: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ScryfallFace {
+    name: String,
+    #[serde(default)]
+    type_line: String,
+    mana_cost: Option<String>,
+    image_uris: Option<ScryfallImages>,
+    colors: Option<Vec<String>>,
+    oracle_text: Option<String>,
+}
+
+/// The subset of a Scryfall bulk data card we keep.
+#[derive(Debug, Deserialize)]
+struct ScryfallCard {
+    arena_id: Option<i32>,
+    name: String,
+    set: String,
+    lang: String,
+    image_uris: Option<ScryfallImages>,
+    #[serde(default)]
+    mana_cost: String,
+    #[serde(default)]
+    cmc: f32,
+    #[serde(default)]
+    type_line: String,
+    layout: String,
+    colors: Option<Vec<String>>,
+    #[serde(default)]
+    color_identity: Vec<String>,
+    card_faces: Option<Vec<ScryfallFace>>,
+    #[serde(default)]
+    rarity: String,
+    #[serde(default)]
+    collector_number: String,
+    #[serde(default)]
+    oracle_text: String,
+}
+
+impl ScryfallCard {
+    fn into_overlay_card(self) -> Option<OverlayCard> {
+        Some(OverlayCard {
+            id: self.arena_id?,
+            set: self.set,
+            name: self.name,
+            lang: self.lang,
+            image_uri: self.image_uris.and_then(|images| images.normal),
+            mana_cost: self.mana_cost,
+            cmc: self.cmc,
+            type_line: self.type_line,
+            layout: self.layout,
+            colors: self.colors,
+            color_identity: self.color_identity,
+            card_faces: self.card_faces.map(|faces| {
+                faces
+                    .into_iter()
+                    .map(|face| OverlayFace {
+                        name: face.name,
+                        type_line: face.type_line,
+                        mana_cost: face.mana_cost,
+                        image_uri: face.image_uris.and_then(|images| images.normal),
+                        colors: face.colors,
+                        oracle_text: face.oracle_text,
+                    })
+                    .collect()
+            }),
+            rarity: self.rarity,
+            collector_number: self.collector_number,
+            oracle_text: self.oracle_text,
+        })
+    }
+}
+
+/// Goes through the bulk file's array one card at a time, `all_cards` runs into gigabytes and
+/// only a few of its cards are on Arena.
+struct ScryfallBulkVisitor;
+
+impl<'de> Visitor<'de> for ScryfallBulkVisitor {
+    type Value = Vec<OverlayCard>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an array of Scryfall cards")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut cards = Vec::new();
+        while let Some(card) = seq.next_element::<ScryfallCard>()? {
+            if card.lang == "en" {
+                cards.extend(card.into_overlay_card());
+            }
+        }
+        Ok(cards)
+    }
+}
+
+/// Cards with an `arena_id` from a Scryfall bulk data file, `default_cards` or `all_cards`.
+pub(crate) fn from_scryfall_bulk(path: &Path) -> anyhow::Result<Vec<OverlayCard>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    let cards = deserializer.deserialize_seq(ScryfallBulkVisitor)?;
+    deserializer.end()?;
+    Ok(cards)
+}
+
+/// `o2oWoW` into `{2}{W}{W}`.
+fn mana_cost_from_mtga(mana_text: &str) -> String {
+    let mut mana_cost = String::new();
+    for symbol in mana_text.split('o').filter(|symbol| !symbol.is_empty()) {
+        mana_cost.push('{');
+        mana_cost.push_str(symbol.trim_matches(|c| c == '(' || c == ')'));
+        mana_cost.push('}');
+    }
+    mana_cost
+}
+
+fn mana_value(mana_cost: &str) -> f32 {
+    mana_symbols(mana_cost)
+        .iter()
+        .map(|symbol| match symbol.parse::<u8>() {
+            Ok(generic) => f32::from(generic),
+            Err(_) if symbol == "X" => 0.0,
+            Err(_) => 1.0,
+        })
+        .sum()
+}
+
+fn rarity_from_mtga(rarity: i32) -> &'static str {
+    match rarity {
+        1 | 2 => "common",
+        3 => "uncommon",
+        4 => "rare",
+        5 => "mythic",
+        _ => "",
+    }
+}
+
+/// Names of the client's `CardType`, `SubType` and `SuperType` enums.
+fn mtga_enum_names(conn: &Connection) -> rusqlite::Result<HashMap<(String, i32), String>> {
+    let mut statement = conn.prepare(indoc! {r"
+        SELECT e.Type, e.Value, MIN(l.Loc)
+        FROM Enums e JOIN Localizations_enUS l ON l.LocId = e.LocId
+        GROUP BY e.Type, e.Value
+    "})?;
+    let names = statement.query_map([], |row| Ok(((row.get(0)?, row.get(1)?), row.get(2)?)))?;
+    names.collect()
+}
+
+/// Cards from the client's `Raw_CardDatabase_*.mtga` `SQLite` file. It has no images and
+/// describes each face as its own card, but knows about every id the client can show us.
+pub(crate) fn from_mtga_database(path: &Path) -> anyhow::Result<Vec<OverlayCard>> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let enum_names = mtga_enum_names(&conn)?;
+    let enum_list = |enum_type: &str, values: &str| -> Vec<String> {
+        values
+            .split(',')
+            .filter_map(|value| value.trim().parse().ok())
+            .filter_map(|value| enum_names.get(&(enum_type.to_string(), value)).cloned())
+            .collect()
+    };
+
+    let mut statement = conn.prepare(indoc! {r"
+        SELECT c.GrpId, c.ExpansionCode, c.Rarity, c.CollectorNumber, c.OldSchoolManaText,
+            c.Types, c.Subtypes, c.Supertypes, MIN(l.Loc)
+        FROM Cards c JOIN Localizations_enUS l ON l.LocId = c.TitleId
+        WHERE c.IsToken = 0
+        GROUP BY c.GrpId
+    "})?;
+    let cards = statement.query_map([], |row| {
+        let mana_cost = mana_cost_from_mtga(&row.get::<_, String>(4)?);
+        let colors: Vec<String> = ["W", "U", "B", "R", "G"]
+            .into_iter()
+            .filter(|color| mana_cost.contains(color))
+            .map(str::to_string)
+            .collect();
+        let types = [
+            enum_list("SuperType", &row.get::<_, String>(7)?),
+            enum_list("CardType", &row.get::<_, String>(5)?),
+        ]
+        .concat()
+        .join(" ");
+        let subtypes = enum_list("SubType", &row.get::<_, String>(6)?).join(" ");
+        let type_line = if subtypes.is_empty() {
+            types
+        } else {
+            format!("{types} \u{2014} {subtypes}")
+        };
+        Ok(OverlayCard {
+            id: row.get(0)?,
+            set: row.get::<_, String>(1)?.to_lowercase(),
+            name: row.get(8)?,
+            lang: "en".to_string(),
+            image_uri: None,
+            cmc: mana_value(&mana_cost),
+            mana_cost,
+            type_line,
+            layout: "normal".to_string(),
+            colors: Some(colors.clone()),
+            color_identity: colors,
+            card_faces: None,
+            rarity: rarity_from_mtga(row.get(2)?).to_string(),
+            collector_number: row.get(3)?,
+            oracle_text: String::new(),
+        })
+    })?;
+    Ok(cards.collect::<rusqlite::Result<_>>()?)
+}
+
+pub(crate) fn read_overlay(path: &Path) -> anyhow::Result<HashMap<String, OverlayCard>> {
+    if !path.exists() {
+        return Ok(HashMap::default());
+    }
+    let reader = BufReader::new(File::open(path)?);
+    Ok(serde_json::from_reader(reader)?)
+}
+
+pub(crate) fn write_overlay(
+    path: &Path,
+    overlay: &HashMap<String, OverlayCard>,
+) -> anyhow::Result<()> {
+    // written aside and renamed so a crash mid-write keeps the previous imports
+    let tmp_path = path.with_extension("tmp");
+    let mut writer = BufWriter::new(File::create(&tmp_path)?);
+    serde_json::to_writer(&mut writer, overlay)?;
+    writer.into_inner()?.sync_all()?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// Puts the cards into the in-memory database, returns how many ids it didn't know about.
+pub(crate) fn apply<'a>(
+    cards: &mut Cards,
+    imported: impl IntoIterator<Item = &'a OverlayCard>,
+) -> anyhow::Result<usize> {
+    let mut newly_resolved = 0;
+    for card in imported {
+        let entry: CardDbEntry = serde_json::from_value(serde_json::to_value(card)?)?;
+        if cards.get(card.id).is_none() {
+            newly_resolved += 1;
+        }
+        cards.db.db.insert(card.id.to_string(), entry);
+        cards.metadata.insert(
+            card.id,
+            CardMetadata {
+                rarity: card.rarity.clone(),
+                collector_number: card.collector_number.clone(),
+                oracle_text: card.oracle_text.clone(),
+            },
+        );
+    }
+    Ok(newly_resolved)
+}
@@ -0,0 +1,27 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use serde::Deserialize;
+
+/// Fields of cards-full.json that `ap_core`'s `CardDbEntry` doesn't keep.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub(crate) struct CardMetadata {
+    #[serde(default)]
+    pub rarity: String,
+    #[serde(default)]
+    pub collector_number: String,
+    #[serde(default)]
+    pub oracle_text: String,
+}
+
+/// Loads the extra fields of a cards file, read alongside `CardsDatabase` from the same file.
+pub(crate) fn load(cards_path: &Path) -> anyhow::Result<HashMap<i32, CardMetadata>> {
+    let reader = BufReader::new(File::open(cards_path)?);
+    let entries: HashMap<String, CardMetadata> = serde_json::from_reader(reader)?;
+    Ok(entries
+        .into_iter()
+        .filter_map(|(card_id, metadata)| Some((card_id.parse().ok()?, metadata)))
+        .collect())
+}
@@ -0,0 +1,340 @@
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::card::{Card, CardType};
+use crate::cards::Cards;
+
+pub(crate) type CardIndexState = Arc<Mutex<CardIndex>>;
+
+/// How a result matched the query, best first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub(crate) enum MatchKind {
+    Exact,
+    CaseInsensitive,
+    AccentInsensitive,
+    Prefix,
+    WordPrefix,
+    Fuzzy,
+    /// Empty query, only filters applied.
+    Filter,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct CardSearchFilter {
+    /// Any face having the type, an adventure creature is also an instant or sorcery.
+    pub card_type: Option<CardType>,
+    /// Cards with at least all of these colors.
+    pub colors: Option<Vec<String>>,
+    pub min_mana_value: Option<i16>,
+    pub max_mana_value: Option<i16>,
+}
+
+impl CardSearchFilter {
+    fn accepts(&self, card: &Card) -> bool {
+        self.card_type
+            .as_ref()
+            .map_or(true, |card_type| card.all_types().contains(card_type))
+            && self.colors.as_ref().map_or(true, |colors| {
+                colors.iter().all(|color| card.colors.contains(color))
+            })
+            && self
+                .min_mana_value
+                .map_or(true, |min| card.mana_value >= min)
+            && self
+                .max_mana_value
+                .map_or(true, |max| card.mana_value <= max)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct CardSearchResult {
+    pub card_id: i32,
+    pub card: Card,
+    pub match_kind: MatchKind,
+    /// Edit distance for fuzzy matches, zero otherwise.
+    pub distance: usize,
+}
+
+#[derive(Debug)]
+struct IndexedCard {
+    card_id: i32,
+    card: Card,
+    lowercase: String,
+    folded: String,
+}
+
+/// Every distinct card name in the cards database, one printing each.
+#[derive(Debug, Default)]
+pub(crate) struct CardIndex {
+    cards: Vec<IndexedCard>,
+}
+
+/// Lowercase with diacritics dropped, so `Æther Vial` and `Lim-Dûl` can be typed plainly.
+pub(crate) fn fold(name: &str) -> String {
+    let mut folded = String::with_capacity(name.len());
+    for c in name.to_lowercase().chars() {
+        match c {
+            'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => folded.push('a'),
+            'æ' => folded.push_str("ae"),
+            'ç' => folded.push('c'),
+            'è' | 'é' | 'ê' | 'ë' => folded.push('e'),
+            'ì' | 'í' | 'î' | 'ï' => folded.push('i'),
+            'ñ' => folded.push('n'),
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' => folded.push('o'),
+            'ù' | 'ú' | 'û' | 'ü' => folded.push('u'),
+            'ý' | 'ÿ' => folded.push('y'),
+            _ => folded.push(c),
+        }
+    }
+    folded
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// How far `query` is from what it was probably meant to be in `name`: the whole name, or as many
+/// characters as were typed from the start of any of its words.
+fn fuzzy_distance(query: &str, name: &str) -> usize {
+    let query_len = query.chars().count();
+    let word_starts = name
+        .char_indices()
+        .filter(|(i, _)| *i == 0 || name[..*i].ends_with([' ', '-', ',']))
+        .map(|(i, _)| i);
+    word_starts
+        .map(|start| {
+            let typed: String = name[start..].chars().take(query_len).collect();
+            edit_distance(query, &typed)
+        })
+        .chain([edit_distance(query, name)])
+        .min()
+        .unwrap_or_default()
+}
+
+impl CardIndex {
+    pub fn new(cards: &Cards) -> Self {
+        let mut entries: Vec<_> = cards.db.db.values().collect();
+        entries.sort_by_key(|entry| entry.id);
+
+        let mut index = Self::default();
+        let mut seen = HashSet::new();
+        for entry in entries {
+            let card = cards.card(entry.id);
+            let folded = fold(&card.name);
+            if !seen.insert(folded.clone()) {
+                continue;
+            }
+            index.cards.push(IndexedCard {
+                card_id: entry.id,
+                lowercase: card.name.to_lowercase(),
+                folded,
+                card,
+            });
+        }
+        index
+    }
+
+    fn match_kind(
+        indexed: &IndexedCard,
+        query: &str,
+        folded_query: &str,
+    ) -> Option<(MatchKind, usize)> {
+        if indexed.card.name == query {
+            Some((MatchKind::Exact, 0))
+        } else if indexed.lowercase == query.to_lowercase() {
+            Some((MatchKind::CaseInsensitive, 0))
+        } else if indexed.folded == folded_query {
+            Some((MatchKind::AccentInsensitive, 0))
+        } else if indexed.folded.starts_with(folded_query) {
+            Some((MatchKind::Prefix, 0))
+        } else if indexed
+            .folded
+            .split([' ', '-', ','])
+            .any(|word| word.starts_with(folded_query))
+        {
+            Some((MatchKind::WordPrefix, 0))
+        } else {
+            // Allow roughly one typo every four characters.
+            let max_distance = (folded_query.chars().count() / 4).max(1);
+            let distance = fuzzy_distance(folded_query, &indexed.folded);
+            (distance <= max_distance).then_some((MatchKind::Fuzzy, distance))
+        }
+    }
+
+    pub fn search(
+        &self,
+        query: &str,
+        filter: &CardSearchFilter,
+        limit: usize,
+    ) -> Vec<CardSearchResult> {
+        let query = query.trim();
+        let folded_query = fold(query);
+
+        let mut results: Vec<(MatchKind, usize, &IndexedCard)> = self
+            .cards
+            .iter()
+            .filter(|indexed| filter.accepts(&indexed.card))
+            .filter_map(|indexed| {
+                if query.is_empty() {
+                    return Some((MatchKind::Filter, 0, indexed));
+                }
+                Self::match_kind(indexed, query, &folded_query)
+                    .map(|(match_kind, distance)| (match_kind, distance, indexed))
+            })
+            .collect();
+        results.sort_by(|a, b| (a.0, a.1, &a.2.card.name).cmp(&(b.0, b.1, &b.2.card.name)));
+        results
+            .into_iter()
+            .take(limit)
+            .map(|(match_kind, distance, indexed)| CardSearchResult {
+                card_id: indexed.card_id,
+                card: indexed.card.clone(),
+                match_kind,
+                distance,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use ap_core::cards::CardsDatabase;
+
+    use super::*;
+    use crate::card_import::{self, OverlayCard};
+
+    fn index(cards: &[(i32, &str, &str)]) -> CardIndex {
+        let cards: Vec<OverlayCard> = cards
+            .iter()
+            .map(|(id, name, mana_cost)| OverlayCard {
+                id: *id,
+                name: (*name).to_string(),
+                lang: "en".to_string(),
+                mana_cost: (*mana_cost).to_string(),
+                colors: Some(
+                    ["W", "U", "B", "R", "G"]
+                        .into_iter()
+                        .filter(|color| mana_cost.contains(color))
+                        .map(str::to_string)
+                        .collect(),
+                ),
+                type_line: "Instant".to_string(),
+                ..OverlayCard::default()
+            })
+            .collect();
+        let mut cards_db = Cards::new(
+            CardsDatabase {
+                db: HashMap::default(),
+            },
+            HashMap::default(),
+        );
+        card_import::apply(&mut cards_db, &cards).expect("Could not build the cards database");
+        CardIndex::new(&cards_db)
+    }
+
+    fn ranked(index: &CardIndex, query: &str) -> Vec<(String, MatchKind, usize)> {
+        index
+            .search(query, &CardSearchFilter::default(), 10)
+            .into_iter()
+            .map(|result| (result.card.name, result.match_kind, result.distance))
+            .collect()
+    }
+
+    fn names(results: &[(String, MatchKind, usize)]) -> Vec<&str> {
+        results.iter().map(|(name, _, _)| name.as_str()).collect()
+    }
+
+    #[test]
+    fn ranks_prefixes_before_word_prefixes_before_typos() {
+        let index = index(&[
+            (1, "Chain Lightning", "{R}"),
+            (2, "Lightning Strike", "{1}{R}"),
+            (3, "Lightning Bolt", "{R}"),
+            (4, "Lighting Bolt", "{R}"),
+            (5, "Opt", "{U}"),
+        ]);
+        let results = ranked(&index, "lightning");
+        assert_eq!(
+            names(&results),
+            [
+                "Lightning Bolt",
+                "Lightning Strike",
+                "Chain Lightning",
+                "Lighting Bolt"
+            ]
+        );
+        assert_eq!(
+            results.iter().map(|(_, kind, _)| *kind).collect::<Vec<_>>(),
+            [
+                MatchKind::Prefix,
+                MatchKind::Prefix,
+                MatchKind::WordPrefix,
+                MatchKind::Fuzzy
+            ]
+        );
+    }
+
+    #[test]
+    fn ranks_exact_before_case_and_accent_insensitive() {
+        let index = index(&[(1, "Lim-Dûl's Vault", "{U}{B}"), (2, "Opt", "{U}")]);
+        assert_eq!(
+            ranked(&index, "Lim-Dûl's Vault"),
+            [("Lim-Dûl's Vault".to_string(), MatchKind::Exact, 0)]
+        );
+        assert_eq!(
+            ranked(&index, "lim-dûl's vault"),
+            [("Lim-Dûl's Vault".to_string(), MatchKind::CaseInsensitive, 0)]
+        );
+        assert_eq!(
+            ranked(&index, "lim-dul's vault"),
+            [(
+                "Lim-Dûl's Vault".to_string(),
+                MatchKind::AccentInsensitive,
+                0
+            )]
+        );
+    }
+
+    #[test]
+    fn matches_typos_against_the_start_of_any_word() {
+        let index = index(&[(1, "Lightning Bolt", "{R}"), (2, "Opt", "{U}")]);
+        assert_eq!(
+            ranked(&index, "bolr"),
+            [("Lightning Bolt".to_string(), MatchKind::Fuzzy, 1)]
+        );
+        assert_eq!(
+            ranked(&index, "lightnig"),
+            [("Lightning Bolt".to_string(), MatchKind::Fuzzy, 1)]
+        );
+        assert!(ranked(&index, "thunder").is_empty());
+    }
+
+    #[test]
+    fn lists_filtered_cards_for_an_empty_query() {
+        let index = index(&[(1, "Lightning Bolt", "{R}"), (2, "Opt", "{U}")]);
+        let filter = CardSearchFilter {
+            colors: Some(vec!["U".to_string()]),
+            ..CardSearchFilter::default()
+        };
+        let results: Vec<_> = index
+            .search("", &filter, 10)
+            .into_iter()
+            .map(|result| (result.card_id, result.match_kind))
+            .collect();
+        assert_eq!(results, [(2, MatchKind::Filter)]);
+    }
+}
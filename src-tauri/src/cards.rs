@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+
+use ap_core::cards::{CardDbEntry, CardsDatabase};
+
+use crate::card::Card;
+use crate::card_metadata::CardMetadata;
+
+/// `ap_core`'s cards with the fields of cards-full.json it doesn't keep. Both are read from the
+/// same files and updated together by imports, so they are kept and locked as one.
+pub(crate) struct Cards {
+    pub db: CardsDatabase,
+    pub metadata: HashMap<i32, CardMetadata>,
+}
+
+impl Cards {
+    pub fn new(db: CardsDatabase, metadata: HashMap<i32, CardMetadata>) -> Self {
+        Self { db, metadata }
+    }
+
+    pub fn get(&self, card_id: i32) -> Option<&CardDbEntry> {
+        self.db.get(&card_id)
+    }
+
+    /// A copy for a `MatchInsightDB`, which owns the cards it reads.
+    pub fn copy_database(&self) -> CardsDatabase {
+        CardsDatabase {
+            db: self.db.db.clone(),
+        }
+    }
+
+    pub fn metadata(&self, card_id: i32) -> Option<&CardMetadata> {
+        self.metadata.get(&card_id)
+    }
+
+    /// The card with its metadata, or a card named after its id when we don't know it.
+    pub fn card(&self, card_id: i32) -> Card {
+        self.get(card_id).map_or_else(
+            || Card::new(card_id.to_string()),
+            |entry| Card::from_entry(entry, self.metadata(card_id)),
+        )
+    }
+}
@@ -0,0 +1,271 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use serde_json::Value;
+
+/// A single message the MTGA client wrote to Player.log.
+#[derive(Debug, Clone)]
+pub(crate) enum ClientLogEntry {
+    /// `==> Method {...}` requests sent by the client to the front door.
+    Request { method: String, payload: Value },
+    /// `<== Method(uuid)` responses, the payload is on the same or the following line. Server
+    /// pushes such as `Draft.Notify {...}` are reported as responses too.
+    Response { method: String, payload: Value },
+    /// Bare JSON lines, e.g. GRE and match game room messages.
+    Message(Value),
+}
+
+/// Messages the server pushes without a request, logged as `Method {...}`.
+const SERVER_PUSHES: [&str; 2] = ["Draft.Notify", "RankUpdated"];
+
+/// Formats the client stamps its messages with, these follow the system locale.
+const TIMESTAMP_FORMATS: [&str; 4] = [
+    "%m/%d/%Y %I:%M:%S %p",
+    "%d/%m/%Y %H:%M:%S",
+    "%d.%m.%Y %H:%M:%S",
+    "%Y-%m-%d %H:%M:%S",
+];
+
+/// Requests and some responses wrap their body as a JSON encoded string under `key`.
+pub(crate) fn embedded_json(payload: &Value, key: &str) -> Option<Value> {
+    payload
+        .get(key)
+        .and_then(Value::as_str)
+        .and_then(|body| serde_json::from_str(body).ok())
+}
+
+/// Tails Player.log line by line, alongside `PlayerLogProcessor`, for the messages `ap_core`
+/// does not surface.
+pub(crate) struct ClientLogReader {
+    reader: BufReader<File>,
+    partial_line: String,
+    pending_response: Option<String>,
+    logged_at: Option<DateTime<Utc>>,
+}
+
+impl ClientLogReader {
+    pub fn try_new(player_log_path: &Path) -> std::io::Result<Self> {
+        let file = File::open(player_log_path)?;
+        Ok(Self {
+            reader: BufReader::new(file),
+            partial_line: String::new(),
+            pending_response: None,
+            logged_at: None,
+        })
+    }
+
+    /// When the client logged the last entry, i.e. the latest timestamp read so far. Replaying
+    /// the same log gives the same timestamps, unlike the time it is read at.
+    pub fn logged_at(&self) -> Option<DateTime<Utc>> {
+        self.logged_at
+    }
+
+    fn next_line(&mut self) -> Option<String> {
+        match self.reader.read_line(&mut self.partial_line) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => {
+                if !self.partial_line.ends_with('\n') {
+                    // the client is mid-write, pick the rest up on the next poll
+                    return None;
+                }
+                let line = self.partial_line.trim().to_string();
+                self.partial_line.clear();
+                Some(line)
+            }
+        }
+    }
+
+    pub fn next_entry(&mut self) -> Option<ClientLogEntry> {
+        while let Some(line) = self.next_line() {
+            if let Some(entry) = self.parse_line(&line) {
+                return Some(entry);
+            }
+        }
+        None
+    }
+
+    fn parse_line(&mut self, line: &str) -> Option<ClientLogEntry> {
+        if let Some(logged_at) = line_timestamp(line) {
+            self.logged_at = Some(logged_at);
+        }
+
+        if let Some(method) = self.pending_response.take() {
+            if let Ok(payload) = serde_json::from_str::<Value>(line) {
+                return Some(ClientLogEntry::Response { method, payload });
+            }
+        }
+
+        if let Some(rest) = marker_suffix(line, "==> ") {
+            let (method, body) = rest.split_once(' ').unwrap_or((rest, ""));
+            return Some(ClientLogEntry::Request {
+                method: method.to_string(),
+                payload: serde_json::from_str(body).unwrap_or(Value::Null),
+            });
+        }
+
+        if let Some(rest) = marker_suffix(line, "<== ") {
+            let (method, body) = rest.split_once(' ').unwrap_or((rest, ""));
+            let method = method.split('(').next().unwrap_or(method).to_string();
+            if let Ok(payload) = serde_json::from_str::<Value>(body) {
+                return Some(ClientLogEntry::Response { method, payload });
+            }
+            self.pending_response = Some(method);
+            return None;
+        }
+
+        for method in SERVER_PUSHES {
+            if let Some(body) = marker_suffix(line, &format!("{method} ")) {
+                return serde_json::from_str(body)
+                    .ok()
+                    .map(|payload| ClientLogEntry::Response {
+                        method: method.to_string(),
+                        payload,
+                    });
+            }
+        }
+
+        if line.starts_with('{') {
+            return serde_json::from_str(line).ok().map(ClientLogEntry::Message);
+        }
+        None
+    }
+}
+
+/// Drops the `[UnityCrossThreadLogger]` style tag the client puts in front of its messages.
+fn log_message(line: &str) -> &str {
+    line.strip_prefix('[')
+        .and_then(|rest| rest.split_once(']'))
+        .map_or(line, |(_, message)| message)
+        .trim_start()
+}
+
+/// The timestamp of lines such as `[UnityCrossThreadLogger]10/18/2026 8:44:00 PM: ...`, logged
+/// in local time.
+fn line_timestamp(line: &str) -> Option<DateTime<Utc>> {
+    if line.starts_with('{') {
+        return None;
+    }
+    let message = log_message(line);
+    let timestamp = TIMESTAMP_FORMATS.iter().find_map(|format| {
+        NaiveDateTime::parse_and_remainder(message, format)
+            .ok()
+            .map(|(timestamp, _)| timestamp)
+    })?;
+    Local
+        .from_local_datetime(&timestamp)
+        .earliest()
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+}
+
+/// What follows `marker` when the message starts with it, so payloads that merely contain the
+/// marker aren't mistaken for one.
+fn marker_suffix<'a>(line: &'a str, marker: &str) -> Option<&'a str> {
+    log_message(line).strip_prefix(marker)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use serde_json::json;
+
+    use super::*;
+
+    /// Reads `log` back as Player.log would be, the file named after the test using it.
+    fn entries(test_name: &str, log: &str) -> Vec<ClientLogEntry> {
+        let path: PathBuf = std::env::temp_dir().join(format!(
+            "arena-buddy-{}-{test_name}.log",
+            std::process::id()
+        ));
+        fs::write(&path, log).expect("Could not write test log");
+        let mut reader = ClientLogReader::try_new(&path).expect("Could not open test log");
+        let entries = std::iter::from_fn(|| reader.next_entry()).collect();
+        fs::remove_file(&path).expect("Could not remove test log");
+        entries
+    }
+
+    #[test]
+    fn parses_requests() {
+        let entries = entries(
+            "requests",
+            "[UnityCrossThreadLogger]==> Event_Join {\"id\":\"1\",\"request\":\"{}\"}\n",
+        );
+        let [ClientLogEntry::Request { method, payload }] = entries.as_slice() else {
+            panic!("expected one request, got {entries:?}");
+        };
+        assert_eq!(method, "Event_Join");
+        assert_eq!(payload["id"], "1");
+    }
+
+    #[test]
+    fn parses_responses_on_the_same_or_the_following_line() {
+        let entries = entries(
+            "responses",
+            indoc::indoc! {r#"
+                <== Rank_GetCombinedRankInfo(0a1b) {"constructedClass":"Gold"}
+                <== Draft_MakeHumanDraftPick(2c3d)
+                {"IsPickSuccessful":true}
+            "#},
+        );
+        let methods: Vec<(&str, &Value)> = entries
+            .iter()
+            .map(|entry| match entry {
+                ClientLogEntry::Response { method, payload } => (method.as_str(), payload),
+                _ => panic!("expected responses, got {entry:?}"),
+            })
+            .collect();
+        assert_eq!(
+            methods,
+            [
+                (
+                    "Rank_GetCombinedRankInfo",
+                    &json!({"constructedClass": "Gold"})
+                ),
+                (
+                    "Draft_MakeHumanDraftPick",
+                    &json!({"IsPickSuccessful": true})
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_server_pushes_as_responses() {
+        let entries = entries(
+            "pushes",
+            "[UnityCrossThreadLogger]Draft.Notify {\"draftId\":\"d\",\"SelfPick\":1}\n",
+        );
+        let [ClientLogEntry::Response { method, payload }] = entries.as_slice() else {
+            panic!("expected one response, got {entries:?}");
+        };
+        assert_eq!(method, "Draft.Notify");
+        assert_eq!(payload["draftId"], "d");
+    }
+
+    #[test]
+    fn parses_bare_json_as_messages() {
+        let entries = entries("messages", "{\"greToClientEvent\":{}}\nnot json at all\n");
+        let [ClientLogEntry::Message(message)] = entries.as_slice() else {
+            panic!("expected one message, got {entries:?}");
+        };
+        assert!(message.get("greToClientEvent").is_some());
+    }
+
+    #[test]
+    fn ignores_markers_inside_payloads() {
+        let entries = entries(
+            "markers",
+            "[UnityCrossThreadLogger]STATE CHANGED {\"note\":\"==> Event_Join {}\"}\n",
+        );
+        assert!(entries.is_empty(), "got {entries:?}");
+    }
+
+    #[test]
+    fn waits_for_the_rest_of_a_partial_line() {
+        let entries = entries("partial", "{\"greToClientEvent\":");
+        assert!(entries.is_empty(), "got {entries:?}");
+    }
+}
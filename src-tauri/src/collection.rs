@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use indoc::indoc;
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::client_log::ClientLogEntry;
+
+/// Currencies from the client's `InventoryInfo` payloads.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Inventory {
+    pub gems: i64,
+    pub gold: i64,
+    pub vault_progress: f64,
+    pub wildcard_common: i64,
+    pub wildcard_uncommon: i64,
+    pub wildcard_rare: i64,
+    pub wildcard_mythic: i64,
+}
+
+impl Inventory {
+    fn from_payload(inventory: &Value) -> Option<Self> {
+        let field = |key: &str| inventory.get(key).and_then(Value::as_i64);
+        Some(Self {
+            gems: field("Gems")?,
+            gold: field("Gold")?,
+            vault_progress: inventory
+                .get("TotalVaultProgress")
+                .and_then(Value::as_f64)
+                .unwrap_or_default(),
+            wildcard_common: field("WildCardCommons").unwrap_or_default(),
+            wildcard_uncommon: field("WildCardUnCommons").unwrap_or_default(),
+            wildcard_rare: field("WildCardRares").unwrap_or_default(),
+            wildcard_mythic: field("WildCardMythics").unwrap_or_default(),
+        })
+    }
+
+    /// Currency gained (positive) or spent (negative) going from `self` to `other`.
+    pub fn change_to(&self, other: &Self) -> Self {
+        Self {
+            gems: other.gems - self.gems,
+            gold: other.gold - self.gold,
+            vault_progress: other.vault_progress - self.vault_progress,
+            wildcard_common: other.wildcard_common - self.wildcard_common,
+            wildcard_uncommon: other.wildcard_uncommon - self.wildcard_uncommon,
+            wildcard_rare: other.wildcard_rare - self.wildcard_rare,
+            wildcard_mythic: other.wildcard_mythic - self.wildcard_mythic,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum CollectionEvent {
+    Inventory(Inventory),
+    Cards(HashMap<i32, u16>),
+}
+
+/// `{"67330": 4, ...}` card id to owned copies.
+fn cards_from_payload(cards: &Value) -> Option<HashMap<i32, u16>> {
+    let cards = cards.as_object()?;
+    Some(
+        cards
+            .iter()
+            .filter_map(|(card_id, quantity)| {
+                let quantity = quantity.as_u64().and_then(|q| u16::try_from(q).ok())?;
+                Some((card_id.parse().ok()?, quantity))
+            })
+            .collect(),
+    )
+}
+
+impl CollectionEvent {
+    pub fn from_entry(entry: &ClientLogEntry) -> Vec<Self> {
+        let ClientLogEntry::Response { method, payload } = entry else {
+            return Vec::new();
+        };
+        let mut events = Vec::new();
+        if let Some(inventory) = payload
+            .get("InventoryInfo")
+            .and_then(Inventory::from_payload)
+        {
+            events.push(Self::Inventory(inventory));
+        }
+        let cards = match method.as_str() {
+            "PlayerInventory.GetPlayerCardsV3" => Some(payload),
+            _ => payload.get("PlayerCards"),
+        };
+        if let Some(cards) = cards.and_then(cards_from_payload) {
+            if !cards.is_empty() {
+                events.push(Self::Cards(cards));
+            }
+        }
+        events
+    }
+
+    /// Whether a snapshot logged at `logged_at` already holds this, i.e. the same log is being
+    /// read again.
+    fn recorded_at(&self, conn: &Connection, logged_at: DateTime<Utc>) -> rusqlite::Result<bool> {
+        let snapshot_ids: Vec<i64> = {
+            let mut statement =
+                conn.prepare("SELECT id FROM collection_snapshots WHERE created_at = ?1")?;
+            let rows = statement.query_map([logged_at], |row| row.get(0))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+        for snapshot_id in snapshot_ids {
+            let recorded = match self {
+                Self::Inventory(inventory) => {
+                    snapshot_inventory(conn, snapshot_id)?.as_ref() == Some(inventory)
+                }
+                Self::Cards(cards) => {
+                    carries_cards(conn, snapshot_id)?
+                        && snapshot_cards(conn, snapshot_id)? == *cards
+                }
+            };
+            if recorded {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Starts a new snapshot whenever currency or cards changed. Snapshots only carry cards when
+    /// the client sent its collection, otherwise the previous collection still stands.
+    pub fn write(&self, conn: &mut Connection, logged_at: DateTime<Utc>) -> rusqlite::Result<()> {
+        let tx = conn.transaction()?;
+        if self.recorded_at(&tx, logged_at)? {
+            return Ok(());
+        }
+        let latest_id = latest_snapshot_id(&tx)?;
+        let latest_inventory = match latest_id {
+            Some(id) => snapshot_inventory(&tx, id)?,
+            None => None,
+        };
+        match self {
+            Self::Inventory(inventory) => {
+                if latest_inventory.as_ref() != Some(inventory) {
+                    insert_snapshot(&tx, logged_at, inventory)?;
+                }
+            }
+            Self::Cards(cards) => {
+                let latest_cards = match latest_id {
+                    Some(id) => snapshot_cards(&tx, id)?,
+                    None => HashMap::new(),
+                };
+                if latest_cards != *cards {
+                    let snapshot_id =
+                        insert_snapshot(&tx, logged_at, &latest_inventory.unwrap_or_default())?;
+                    let mut statement = tx.prepare(
+                        "INSERT INTO collection_cards (snapshot_id, card_id, quantity) VALUES (?1, ?2, ?3)",
+                    )?;
+                    for (card_id, quantity) in cards {
+                        statement.execute((snapshot_id, card_id, quantity))?;
+                    }
+                }
+            }
+        }
+        tx.commit()
+    }
+}
+
+fn insert_snapshot(
+    conn: &Connection,
+    logged_at: DateTime<Utc>,
+    inventory: &Inventory,
+) -> rusqlite::Result<i64> {
+    conn.execute(
+        indoc! {r"
+            INSERT INTO collection_snapshots (
+                created_at, gems, gold, vault_progress, wildcard_common, wildcard_uncommon,
+                wildcard_rare, wildcard_mythic
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+        "},
+        (
+            logged_at,
+            inventory.gems,
+            inventory.gold,
+            inventory.vault_progress,
+            inventory.wildcard_common,
+            inventory.wildcard_uncommon,
+            inventory.wildcard_rare,
+            inventory.wildcard_mythic,
+        ),
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub(crate) fn latest_snapshot_id(conn: &Connection) -> rusqlite::Result<Option<i64>> {
+    conn.query_row("SELECT MAX(id) FROM collection_snapshots", [], |row| {
+        row.get(0)
+    })
+}
+
+pub(crate) fn snapshot_inventory(
+    conn: &Connection,
+    snapshot_id: i64,
+) -> rusqlite::Result<Option<Inventory>> {
+    conn.query_row(
+        indoc! {r"
+            SELECT gems, gold, vault_progress, wildcard_common, wildcard_uncommon, wildcard_rare,
+                wildcard_mythic
+            FROM collection_snapshots WHERE id = ?1
+        "},
+        [snapshot_id],
+        |row| {
+            Ok(Inventory {
+                gems: row.get(0)?,
+                gold: row.get(1)?,
+                vault_progress: row.get(2)?,
+                wildcard_common: row.get(3)?,
+                wildcard_uncommon: row.get(4)?,
+                wildcard_rare: row.get(5)?,
+                wildcard_mythic: row.get(6)?,
+            })
+        },
+    )
+    .optional()
+}
+
+fn carries_cards(conn: &Connection, snapshot_id: i64) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM collection_cards WHERE snapshot_id = ?1)",
+        [snapshot_id],
+        |row| row.get(0),
+    )
+}
+
+/// The collection as of `snapshot_id`, i.e. the cards of the latest snapshot at or before it
+/// that carried any.
+pub(crate) fn snapshot_cards(
+    conn: &Connection,
+    snapshot_id: i64,
+) -> rusqlite::Result<HashMap<i32, u16>> {
+    let mut statement = conn.prepare(indoc! {r"
+        SELECT card_id, quantity FROM collection_cards
+        WHERE snapshot_id = (
+            SELECT MAX(snapshot_id) FROM collection_cards WHERE snapshot_id <= ?1
+        )
+    "})?;
+    let cards = statement.query_map([snapshot_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    cards.collect()
+}
@@ -0,0 +1,117 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::annotations::{self, MatchNote};
+use crate::commands::blocking;
+use crate::commands::error::CommandError;
+use crate::database::DatabaseState;
+use crate::search;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct MatchAnnotations {
+    notes: Vec<MatchNote>,
+    tags: BTreeSet<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct TagCount {
+    tag: String,
+    matches: u32,
+}
+
+#[tauri::command]
+pub(crate) async fn command_annotations(
+    match_id: String,
+    db: State<'_, DatabaseState>,
+) -> Result<MatchAnnotations, CommandError> {
+    blocking::read(&db, move |db| {
+        Ok(MatchAnnotations {
+            notes: annotations::notes(&db.conn, &match_id)?,
+            tags: annotations::tags(&db.conn, &match_id)?,
+        })
+    })
+    .await
+}
+
+#[tauri::command]
+pub(crate) async fn command_add_note(
+    match_id: String,
+    author: Option<String>,
+    body: String,
+    db: State<'_, DatabaseState>,
+) -> Result<MatchNote, CommandError> {
+    blocking::write(&db, move |db| {
+        let note = annotations::add_note(&db.conn, &match_id, author.as_deref(), &body)?;
+        search::index_match(db, &match_id)?;
+        Ok(note)
+    })
+    .await
+}
+
+#[tauri::command]
+pub(crate) async fn command_edit_note(
+    note_id: i64,
+    body: String,
+    db: State<'_, DatabaseState>,
+) -> Result<MatchNote, CommandError> {
+    blocking::write(&db, move |db| {
+        let note = annotations::edit_note(&db.conn, note_id, &body)?;
+        search::index_match(db, &note.match_id)?;
+        Ok(note)
+    })
+    .await
+}
+
+#[tauri::command]
+pub(crate) async fn command_remove_note(
+    note_id: i64,
+    db: State<'_, DatabaseState>,
+) -> Result<(), CommandError> {
+    blocking::write(&db, move |db| {
+        let match_id = annotations::remove_note(&db.conn, note_id)?;
+        Ok(search::index_match(db, &match_id)?)
+    })
+    .await
+}
+
+/// Tags `match_id`, returning all of its tags.
+#[tauri::command]
+pub(crate) async fn command_add_tag(
+    match_id: String,
+    tag: String,
+    db: State<'_, DatabaseState>,
+) -> Result<BTreeSet<String>, CommandError> {
+    blocking::write(&db, move |db| {
+        Ok(annotations::add_tag(&db.conn, &match_id, &tag)?)
+    })
+    .await
+}
+
+/// Untags `match_id`, returning the tags it has left.
+#[tauri::command]
+pub(crate) async fn command_remove_tag(
+    match_id: String,
+    tag: String,
+    db: State<'_, DatabaseState>,
+) -> Result<BTreeSet<String>, CommandError> {
+    blocking::write(&db, move |db| {
+        Ok(annotations::remove_tag(&db.conn, &match_id, &tag)?)
+    })
+    .await
+}
+
+/// Every tag in use, most used first, for the filter pickers.
+#[tauri::command]
+pub(crate) async fn command_tags(
+    db: State<'_, DatabaseState>,
+) -> Result<Vec<TagCount>, CommandError> {
+    blocking::read(&db, |db| {
+        Ok(annotations::tag_counts(&db.conn)?
+            .into_iter()
+            .map(|(tag, matches)| TagCount { tag, matches })
+            .collect())
+    })
+    .await
+}
@@ -0,0 +1,56 @@
+use std::path::PathBuf;
+
+use tauri::{AppHandle, Manager, State};
+
+use crate::backups::{self, BackupInfo, BackupReason};
+use crate::commands::blocking;
+use crate::commands::error::CommandError;
+use crate::database::DatabaseState;
+use crate::search;
+
+fn get_backups_dir(app: &AppHandle) -> Result<PathBuf, CommandError> {
+    app.path()
+        .app_data_dir()
+        .map(|app_data_dir| backups::backups_dir(&app_data_dir))
+        .map_err(|e| CommandError::Database(e.to_string()))
+}
+
+/// Newest first.
+#[tauri::command]
+pub(crate) async fn command_backups(app: AppHandle) -> Result<Vec<BackupInfo>, CommandError> {
+    let backups_dir = get_backups_dir(&app)?;
+    blocking::run(move || Ok(backups::list(&backups_dir)?)).await
+}
+
+#[tauri::command]
+pub(crate) async fn command_create_backup(
+    app: AppHandle,
+    db: State<'_, DatabaseState>,
+) -> Result<BackupInfo, CommandError> {
+    let backups_dir = get_backups_dir(&app)?;
+    blocking::read(&db, move |db| {
+        Ok(backups::create(
+            &db.conn,
+            &backups_dir,
+            BackupReason::Manual,
+        )?)
+    })
+    .await
+}
+
+/// Returns the backup taken of the current state just before restoring.
+#[tauri::command]
+pub(crate) async fn command_restore_backup(
+    file_name: String,
+    app: AppHandle,
+    db: State<'_, DatabaseState>,
+) -> Result<BackupInfo, CommandError> {
+    let backups_dir = get_backups_dir(&app)?;
+    blocking::write(&db, move |db| {
+        let safety_backup = backups::restore(&mut db.conn, &backups_dir, &file_name)?;
+        // The backup may predate the search index or some of its matches.
+        search::index_missing(db)?;
+        Ok(safety_backup)
+    })
+    .await
+}
@@ -0,0 +1,44 @@
+use tauri::async_runtime;
+use tracing::error;
+
+use crate::commands::error::CommandError;
+use crate::database::{DatabaseState, ReadDb, WriteDb};
+
+/// Runs `query` with a pooled reader on the blocking thread pool, keeping `SQLite` off the
+/// thread that serves IPC.
+pub(crate) async fn read<T, F>(db: &DatabaseState, query: F) -> Result<T, CommandError>
+where
+    T: Send + 'static,
+    F: FnOnce(&mut ReadDb<'_>) -> Result<T, CommandError> + Send + 'static,
+{
+    let db = db.clone();
+    async_runtime::spawn_blocking(move || {
+        let mut db = db.read()?;
+        query(&mut db)
+    })
+    .await?
+    .inspect_err(|e| error!("Command failed: {}", e))
+}
+
+/// Like `read`, with the writer connection ingest also uses.
+pub(crate) async fn write<T, F>(db: &DatabaseState, update: F) -> Result<T, CommandError>
+where
+    T: Send + 'static,
+    F: FnOnce(&mut WriteDb<'_>) -> Result<T, CommandError> + Send + 'static,
+{
+    let db = db.clone();
+    async_runtime::spawn_blocking(move || update(&mut db.write()))
+        .await?
+        .inspect_err(|e| error!("Command failed: {}", e))
+}
+
+/// Like `read`, for work that needs no connection, such as listing files.
+pub(crate) async fn run<T, F>(work: F) -> Result<T, CommandError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, CommandError> + Send + 'static,
+{
+    async_runtime::spawn_blocking(work)
+        .await?
+        .inspect_err(|e| error!("Command failed: {}", e))
+}
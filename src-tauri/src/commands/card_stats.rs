@@ -0,0 +1,197 @@
+use std::collections::{HashMap, HashSet};
+
+use ap_core::match_insights::MatchInsightDB;
+use indoc::indoc;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use tracing::info;
+
+use crate::annotations;
+use crate::card::Card;
+use crate::commands::blocking;
+use crate::commands::error::CommandError;
+use crate::database::{DatabaseState, ReadDb};
+use crate::deck::deck_id;
+use crate::match_format::{MatchFilter, MatchFormat};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct WinRate {
+    games: u32,
+    wins: u32,
+    rate: Option<f64>,
+}
+
+impl WinRate {
+    fn record(&mut self, won: bool) {
+        self.games += 1;
+        if won {
+            self.wins += 1;
+        }
+        self.rate = Some(f64::from(self.wins) / f64::from(self.games));
+    }
+}
+
+/// 17lands style metrics for a single card across every tracked game played with a deck.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct CardPerformance {
+    card: Card,
+    opening_hand: WinRate,
+    drawn: WinRate,
+    in_hand: WinRate,
+    not_drawn: WinRate,
+}
+
+struct GameRecord {
+    won: bool,
+    deck: HashMap<i32, u16>,
+    opening_hand: HashSet<i32>,
+    drawn: HashSet<i32>,
+}
+
+fn game_results(db: &MatchInsightDB) -> rusqlite::Result<HashMap<String, Vec<(i32, bool)>>> {
+    let mut statement = db.conn.prepare(indoc! {r#"
+        SELECT mr.match_id, mr.game_number, m.controller_seat_id = mr.winning_team_id
+        FROM matches m JOIN match_results mr ON m.id = mr.match_id
+        WHERE mr.result_scope = "MatchScope_Game"
+            AND m.id NOT IN (SELECT match_id FROM hidden_matches)
+    "#})?;
+    let rows = statement.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, i32>(1)?,
+            row.get::<_, bool>(2)?,
+        ))
+    })?;
+
+    let mut results: HashMap<String, Vec<(i32, bool)>> = HashMap::new();
+    for row in rows {
+        let (match_id, game_number, won) = row?;
+        results
+            .entry(match_id)
+            .or_default()
+            .push((game_number, won));
+    }
+    Ok(results)
+}
+
+fn game_draws(db: &MatchInsightDB, match_id: &str) -> rusqlite::Result<HashMap<i32, HashSet<i32>>> {
+    let mut statement = db
+        .conn
+        .prepare("SELECT game_number, card_id FROM game_draws WHERE match_id = ?1")?;
+    let rows = statement.query_map([match_id], |row| {
+        Ok((row.get::<_, i32>(0)?, row.get::<_, i32>(1)?))
+    })?;
+
+    let mut draws: HashMap<i32, HashSet<i32>> = HashMap::new();
+    for row in rows {
+        let (game_number, card_id) = row?;
+        draws.entry(game_number).or_default().insert(card_id);
+    }
+    Ok(draws)
+}
+
+/// Games of every match whose first game was played with `target_deck_id`. Games played before
+/// draws were tracked are skipped since "never drawn" can't be told apart from "unknown".
+fn game_records(
+    db: &mut MatchInsightDB,
+    target_deck_id: &str,
+    filter: &MatchFilter,
+) -> anyhow::Result<Vec<GameRecord>> {
+    let results = game_results(db)?;
+    let match_formats = MatchFormat::get_all(&db.conn)?;
+    let tags = annotations::tags_by_match(&db.conn)?;
+    let mut records = Vec::new();
+
+    for (match_id, games) in &results {
+        if !filter.accepts(match_formats.get(match_id)) || !filter.accepts_tags(tags.get(match_id))
+        {
+            continue;
+        }
+        let decklists = db.get_decklists(match_id)?;
+        if decklists.first().map(deck_id).as_deref() != Some(target_deck_id) {
+            continue;
+        }
+        let draws_by_game = game_draws(db, match_id)?;
+        let mulligans = db.get_mulligans(match_id)?;
+
+        for (game_number, won) in games {
+            let Some(drawn) = draws_by_game.get(game_number) else {
+                continue;
+            };
+            let deck = usize::try_from(game_number - 1)
+                .ok()
+                .and_then(|index| decklists.get(index))
+                .unwrap_or(&decklists[0]);
+            let opening_hand = mulligans
+                .iter()
+                .find(|m| m.game_number == *game_number && m.decision == "Keep")
+                .map(|m| {
+                    m.hand
+                        .split(',')
+                        .filter_map(|card_id| card_id.parse::<i32>().ok())
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            records.push(GameRecord {
+                won: *won,
+                deck: deck.quantities(),
+                opening_hand,
+                drawn: drawn.clone(),
+            });
+        }
+    }
+    Ok(records)
+}
+
+fn get_card_stats(
+    db: &mut ReadDb<'_>,
+    deck_id: &str,
+    filter: &MatchFilter,
+) -> anyhow::Result<Vec<CardPerformance>> {
+    info!("Getting card stats for deck_id: {}", deck_id);
+    let records = game_records(db, deck_id, filter)?;
+
+    let mut performances: HashMap<i32, CardPerformance> = HashMap::new();
+    for record in &records {
+        for card_id in record.deck.keys() {
+            let performance = performances.entry(*card_id).or_insert_with(|| {
+                let card = db.cards.card(*card_id);
+                CardPerformance {
+                    card,
+                    ..Default::default()
+                }
+            });
+
+            let in_opening_hand = record.opening_hand.contains(card_id);
+            let drawn = !in_opening_hand && record.drawn.contains(card_id);
+            if in_opening_hand {
+                performance.opening_hand.record(record.won);
+            }
+            if drawn {
+                performance.drawn.record(record.won);
+            }
+            if in_opening_hand || drawn {
+                performance.in_hand.record(record.won);
+            } else {
+                performance.not_drawn.record(record.won);
+            }
+        }
+    }
+
+    Ok(performances
+        .into_values()
+        .sorted_by(|a, b| a.card.cmp(&b.card))
+        .collect())
+}
+
+#[tauri::command]
+pub(crate) async fn command_card_stats(
+    deck_id: String,
+    filter: Option<MatchFilter>,
+    db: State<'_, DatabaseState>,
+) -> Result<Vec<CardPerformance>, CommandError> {
+    let filter = filter.unwrap_or_default();
+    blocking::read(&db, move |db| Ok(get_card_stats(db, &deck_id, &filter)?)).await
+}
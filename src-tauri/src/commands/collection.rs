@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+
+use ap_core::match_insights::MatchInsightDB;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::card::Card;
+use crate::cards::Cards;
+use crate::collection::{self, Inventory};
+use crate::commands::blocking;
+use crate::commands::error::CommandError;
+use crate::database::{DatabaseState, ReadDb};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct CollectionSnapshot {
+    id: i64,
+    created_at: DateTime<Utc>,
+    inventory: Inventory,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct Collection {
+    snapshot_id: Option<i64>,
+    inventory: Inventory,
+    cards: Vec<Card>,
+}
+
+/// What changed between two snapshots, currency spent shows up as negative amounts.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct CollectionDiff {
+    currency: Inventory,
+    gained: Vec<Card>,
+    lost: Vec<Card>,
+}
+
+fn resolve_card(card_id: i32, quantity: u16, cards: &Cards) -> Card {
+    let mut card: Card = cards.card(card_id);
+    card.quantity = quantity;
+    card
+}
+
+fn get_collection(db: &ReadDb<'_>) -> rusqlite::Result<Collection> {
+    let Some(snapshot_id) = collection::latest_snapshot_id(&db.conn)? else {
+        return Ok(Collection::default());
+    };
+    let mut cards: Vec<Card> = collection::snapshot_cards(&db.conn, snapshot_id)?
+        .into_iter()
+        .map(|(card_id, quantity)| resolve_card(card_id, quantity, &db.cards))
+        .collect();
+    cards.sort();
+    Ok(Collection {
+        snapshot_id: Some(snapshot_id),
+        inventory: collection::snapshot_inventory(&db.conn, snapshot_id)?.unwrap_or_default(),
+        cards,
+    })
+}
+
+fn get_snapshots(db: &MatchInsightDB) -> rusqlite::Result<Vec<CollectionSnapshot>> {
+    let mut statement = db.conn.prepare(
+        "SELECT id, created_at, gems, gold, vault_progress, wildcard_common, wildcard_uncommon, wildcard_rare, wildcard_mythic FROM collection_snapshots ORDER BY id",
+    )?;
+    let snapshots = statement.query_map([], |row| {
+        Ok(CollectionSnapshot {
+            id: row.get(0)?,
+            created_at: row.get(1)?,
+            inventory: Inventory {
+                gems: row.get(2)?,
+                gold: row.get(3)?,
+                vault_progress: row.get(4)?,
+                wildcard_common: row.get(5)?,
+                wildcard_uncommon: row.get(6)?,
+                wildcard_rare: row.get(7)?,
+                wildcard_mythic: row.get(8)?,
+            },
+        })
+    })?;
+    snapshots.collect()
+}
+
+fn get_diff(db: &ReadDb<'_>, from: i64, to: i64) -> Result<CollectionDiff, CommandError> {
+    let from_inventory = collection::snapshot_inventory(&db.conn, from)?
+        .ok_or_else(|| CommandError::not_found(format!("collection snapshot {from}")))?;
+    let to_inventory = collection::snapshot_inventory(&db.conn, to)?
+        .ok_or_else(|| CommandError::not_found(format!("collection snapshot {to}")))?;
+    let from_cards = collection::snapshot_cards(&db.conn, from)?;
+    let to_cards = collection::snapshot_cards(&db.conn, to)?;
+
+    let mut changes: HashMap<i32, i32> = HashMap::default();
+    for (card_id, quantity) in &to_cards {
+        *changes.entry(*card_id).or_default() += i32::from(*quantity);
+    }
+    for (card_id, quantity) in &from_cards {
+        *changes.entry(*card_id).or_default() -= i32::from(*quantity);
+    }
+
+    let mut diff = CollectionDiff {
+        currency: from_inventory.change_to(&to_inventory),
+        ..Default::default()
+    };
+    for (card_id, change) in changes {
+        let quantity = u16::try_from(change.unsigned_abs()).unwrap_or(u16::MAX);
+        if change > 0 {
+            diff.gained.push(resolve_card(card_id, quantity, &db.cards));
+        } else if change < 0 {
+            diff.lost.push(resolve_card(card_id, quantity, &db.cards));
+        }
+    }
+    diff.gained.sort();
+    diff.lost.sort();
+    Ok(diff)
+}
+
+#[tauri::command]
+pub(crate) async fn command_collection(
+    db: State<'_, DatabaseState>,
+) -> Result<Collection, CommandError> {
+    blocking::read(&db, |db| Ok(get_collection(db)?)).await
+}
+
+#[tauri::command]
+pub(crate) async fn command_collection_snapshots(
+    db: State<'_, DatabaseState>,
+) -> Result<Vec<CollectionSnapshot>, CommandError> {
+    blocking::read(&db, |db| Ok(get_snapshots(db)?)).await
+}
+
+#[tauri::command]
+pub(crate) async fn command_collection_diff(
+    from_snapshot_id: i64,
+    to_snapshot_id: i64,
+    db: State<'_, DatabaseState>,
+) -> Result<CollectionDiff, CommandError> {
+    blocking::read(&db, move |db| {
+        get_diff(db, from_snapshot_id, to_snapshot_id)
+    })
+    .await
+}
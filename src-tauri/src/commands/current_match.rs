@@ -0,0 +1,11 @@
+use tauri::State;
+
+use crate::commands::error::CommandError;
+use crate::current_match::{CurrentMatch, CurrentMatchState};
+
+#[tauri::command]
+pub(crate) async fn command_current_match(
+    current_match: State<'_, CurrentMatchState>,
+) -> Result<Option<CurrentMatch>, CommandError> {
+    Ok(current_match.inner().lock()?.clone())
+}
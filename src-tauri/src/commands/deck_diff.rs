@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::card::{Card, CardType};
+use crate::commands::blocking;
+use crate::commands::error::CommandError;
+use crate::database::{DatabaseState, ReadDb};
+use crate::deck::{DeckDifference, DeckGrouping};
+use crate::decklist::DeckSource;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct DeckDiff {
+    added: HashMap<CardType, Vec<Card>>,
+    removed: HashMap<CardType, Vec<Card>>,
+    /// Lines of either imported text list that could not be matched to a card.
+    unresolved: Vec<String>,
+}
+
+fn get_deck_diff(db: &mut ReadDb<'_>, a: &DeckSource, b: &DeckSource) -> anyhow::Result<DeckDiff> {
+    let a = a.resolve(db)?;
+    let b = b.resolve(db)?;
+    let difference = DeckDifference::difference(&a.deck, &b.deck, &db.cards);
+    Ok(DeckDiff {
+        added: DeckGrouping::PrimaryType.group(difference.added),
+        removed: DeckGrouping::PrimaryType.group(difference.removed),
+        unresolved: a.unresolved.into_iter().chain(b.unresolved).collect(),
+    })
+}
+
+/// Cards `b` adds to or removes from `a`'s main deck.
+#[tauri::command]
+pub(crate) async fn command_deck_diff(
+    a: DeckSource,
+    b: DeckSource,
+    db: State<'_, DatabaseState>,
+) -> Result<DeckDiff, CommandError> {
+    blocking::read(&db, move |db| Ok(get_deck_diff(db, &a, &b)?)).await
+}
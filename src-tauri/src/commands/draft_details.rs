@@ -0,0 +1,93 @@
+use indoc::indoc;
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use tracing::info;
+
+use crate::card::Card;
+use crate::cards::Cards;
+use crate::commands::blocking;
+use crate::commands::error::CommandError;
+use crate::database::{DatabaseState, ReadDb};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct DraftPick {
+    pack_number: i32,
+    pick_number: i32,
+    picked: Option<Card>,
+    pack: Vec<Card>,
+}
+
+impl DraftPick {
+    #[allow(clippy::similar_names)]
+    fn new(
+        pack_number: i32,
+        pick_number: i32,
+        pack_cards: &str,
+        picked_card_id: Option<i32>,
+        cards: &Cards,
+    ) -> Self {
+        let resolve = |card_id: i32| -> Card { cards.card(card_id) };
+        Self {
+            pack_number,
+            pick_number,
+            picked: picked_card_id.map(resolve),
+            pack: pack_cards
+                .split(',')
+                .filter_map(|card_id| card_id.parse::<i32>().ok())
+                .map(resolve)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct DraftDetails {
+    id: String,
+    event_name: String,
+    picks: Vec<DraftPick>,
+}
+
+fn get_draft_details(db: &ReadDb<'_>, draft_id: String) -> Result<DraftDetails, CommandError> {
+    info!("Getting draft details for draft_id: {}", draft_id);
+    let event_name = db
+        .conn
+        .query_row(
+            "SELECT event_name FROM drafts WHERE id = ?1",
+            [&draft_id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .ok_or_else(|| CommandError::not_found(format!("draft {draft_id}")))?;
+
+    let mut statement = db.conn.prepare(indoc! {r"
+        SELECT pack_number, pick_number, pack_cards, picked_card_id
+        FROM draft_picks WHERE draft_id = ?1
+        ORDER BY pack_number, pick_number
+    "})?;
+    let picks = statement
+        .query_map([&draft_id], |row| {
+            Ok(DraftPick::new(
+                row.get(0)?,
+                row.get(1)?,
+                &row.get::<_, String>(2)?,
+                row.get(3)?,
+                &db.cards,
+            ))
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+
+    Ok(DraftDetails {
+        id: draft_id,
+        event_name,
+        picks,
+    })
+}
+
+#[tauri::command]
+pub(crate) async fn command_draft_details(
+    draft_id: String,
+    db: State<'_, DatabaseState>,
+) -> Result<DraftDetails, CommandError> {
+    blocking::read(&db, move |db| get_draft_details(db, draft_id)).await
+}
@@ -0,0 +1,52 @@
+use ap_core::match_insights::MatchInsightDB;
+use chrono::{DateTime, Utc};
+use indoc::indoc;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::commands::blocking;
+use crate::commands::error::CommandError;
+use crate::database::DatabaseState;
+use crate::match_format::MatchFilter;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Draft {
+    id: String,
+    event_name: String,
+    created_at: DateTime<Utc>,
+    picks: i32,
+}
+
+fn get_drafts(db: &MatchInsightDB, filter: &MatchFilter) -> rusqlite::Result<Vec<Draft>> {
+    let mut statement = db.conn.prepare(indoc! {r"
+        SELECT d.id, d.event_name, d.created_at, COUNT(dp.picked_card_id)
+        FROM drafts d LEFT JOIN draft_picks dp ON d.id = dp.draft_id
+        GROUP BY d.id
+        ORDER BY d.created_at DESC
+    "})?;
+    let drafts = statement.query_map([], |row| {
+        Ok(Draft {
+            id: row.get(0)?,
+            event_name: row.get(1)?,
+            created_at: row.get(2)?,
+            picks: row.get(3)?,
+        })
+    })?;
+    let mut accepted = Vec::new();
+    for draft in drafts {
+        let draft = draft?;
+        if filter.accepts_event(&draft.event_name) {
+            accepted.push(draft);
+        }
+    }
+    Ok(accepted)
+}
+
+#[tauri::command]
+pub(crate) async fn command_drafts(
+    filter: Option<MatchFilter>,
+    db: State<'_, DatabaseState>,
+) -> Result<Vec<Draft>, CommandError> {
+    let filter = filter.unwrap_or_default();
+    blocking::read(&db, move |db| Ok(get_drafts(db, &filter)?)).await
+}
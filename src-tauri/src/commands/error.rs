@@ -0,0 +1,74 @@
+use std::error::Error;
+use std::fmt::Display;
+use std::sync::PoisonError;
+
+use serde::{Deserialize, Serialize};
+
+/// Why a command failed, serialized as `{ "kind": "not_found", "message": "..." }` so the
+/// frontend can tell "no data" from "broken" and say which.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+pub(crate) enum CommandError {
+    NotFound(String),
+    Database(String),
+    Lock(String),
+    CardDatabase(String),
+    /// The blocking task running the command panicked or was cancelled.
+    Task(String),
+}
+
+impl CommandError {
+    pub fn not_found(what: impl Display) -> Self {
+        Self::NotFound(what.to_string())
+    }
+}
+
+impl Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound(message) => write!(f, "Not found: {message}"),
+            Self::Database(message) => write!(f, "Database error: {message}"),
+            Self::Lock(message) => write!(f, "Lock error: {message}"),
+            Self::CardDatabase(message) => write!(f, "Card database error: {message}"),
+            Self::Task(message) => write!(f, "Task error: {message}"),
+        }
+    }
+}
+
+impl Error for CommandError {}
+
+impl From<rusqlite::Error> for CommandError {
+    fn from(e: rusqlite::Error) -> Self {
+        match e {
+            rusqlite::Error::QueryReturnedNoRows => Self::NotFound(e.to_string()),
+            _ => Self::Database(e.to_string()),
+        }
+    }
+}
+
+/// `ap_core`'s queries and our own multi-step helpers report through `anyhow`, the latter
+/// wrapping a `CommandError` when they know better than "database".
+impl From<anyhow::Error> for CommandError {
+    fn from(e: anyhow::Error) -> Self {
+        let e = match e.downcast::<Self>() {
+            Ok(e) => return e,
+            Err(e) => e,
+        };
+        match e.downcast::<rusqlite::Error>() {
+            Ok(e) => e.into(),
+            Err(e) => Self::Database(e.to_string()),
+        }
+    }
+}
+
+impl<T> From<PoisonError<T>> for CommandError {
+    fn from(e: PoisonError<T>) -> Self {
+        Self::Lock(e.to_string())
+    }
+}
+
+impl From<tauri::Error> for CommandError {
+    fn from(e: tauri::Error) -> Self {
+        Self::Task(e.to_string())
+    }
+}
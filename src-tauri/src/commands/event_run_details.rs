@@ -0,0 +1,144 @@
+use ap_core::match_insights::MatchInsightDB;
+use ap_core::models::deck::Deck;
+use chrono::{DateTime, Utc};
+use indoc::indoc;
+use itertools::Itertools;
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use tracing::info;
+
+use crate::card::Card;
+use crate::cards::Cards;
+use crate::commands::blocking;
+use crate::commands::error::CommandError;
+use crate::database::{DatabaseState, ReadDb};
+use crate::deck::{DeckDisplayRecord, DeckGrouping};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EventRunMatch {
+    id: String,
+    opponent_player_name: String,
+    did_controller_win: Option<bool>,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct EventRunDetails {
+    course_id: String,
+    event_name: String,
+    current_module: String,
+    wins: i32,
+    losses: i32,
+    prizes: Option<serde_json::Value>,
+    pool: Vec<Card>,
+    deck: Option<DeckDisplayRecord>,
+    matches: Vec<EventRunMatch>,
+}
+
+fn card_ids(card_ids: &str) -> Vec<i32> {
+    card_ids
+        .split(',')
+        .filter_map(|card_id| card_id.parse::<i32>().ok())
+        .collect()
+}
+
+fn pool(card_pool: &str, cards: &Cards) -> Vec<Card> {
+    card_ids(card_pool)
+        .into_iter()
+        .counts()
+        .into_iter()
+        .map(|(card_id, quantity)| {
+            let mut card: Card = cards.card(card_id);
+            card.quantity = u16::try_from(quantity).unwrap_or(u16::MAX);
+            card
+        })
+        .sorted()
+        .collect()
+}
+
+fn get_event_run_matches(
+    db: &MatchInsightDB,
+    course_id: &str,
+) -> rusqlite::Result<Vec<EventRunMatch>> {
+    let mut statement = db.conn.prepare(indoc! {r#"
+        SELECT m.id, m.opponent_player_name, m.controller_seat_id = mr.winning_team_id, m.created_at
+        FROM event_run_matches erm
+        JOIN matches m ON m.id = erm.match_id
+        LEFT JOIN match_results mr ON m.id = mr.match_id AND mr.result_scope = "MatchScope_Match"
+        WHERE erm.course_id = ?1
+        ORDER BY m.created_at
+    "#})?;
+    let matches = statement.query_map([course_id], |row| {
+        Ok(EventRunMatch {
+            id: row.get(0)?,
+            opponent_player_name: row.get(1)?,
+            did_controller_win: row.get(2)?,
+            created_at: row.get(3)?,
+        })
+    })?;
+    matches.collect()
+}
+
+fn get_event_run_details(
+    db: &ReadDb<'_>,
+    course_id: String,
+    grouping: DeckGrouping,
+) -> Result<EventRunDetails, CommandError> {
+    info!("Getting event run details for course_id: {}", course_id);
+    let mut event_run_details = db
+        .conn
+        .query_row(
+            indoc! {r"
+                SELECT event_name, current_module, wins, losses, prizes, card_pool, main_deck, sideboard
+                FROM event_runs WHERE course_id = ?1
+            "},
+            [&course_id],
+            |row| {
+                let prizes: Option<String> = row.get(4)?;
+                let card_pool: String = row.get(5)?;
+                let main_deck: String = row.get(6)?;
+                let sideboard: String = row.get(7)?;
+                let event_name: String = row.get(0)?;
+                let deck = (!main_deck.is_empty()).then(|| {
+                    let deck = Deck::new(
+                        event_name.clone(),
+                        1,
+                        card_ids(&main_deck),
+                        card_ids(&sideboard),
+                    );
+                    DeckDisplayRecord::from_decklist(&deck, &db.cards, grouping)
+                });
+                Ok(EventRunDetails {
+                    course_id: course_id.clone(),
+                    event_name,
+                    current_module: row.get(1)?,
+                    wins: row.get(2)?,
+                    losses: row.get(3)?,
+                    prizes: prizes.and_then(|prizes| serde_json::from_str(&prizes).ok()),
+                    pool: pool(&card_pool, &db.cards),
+                    deck,
+                    matches: Vec::new(),
+                })
+            },
+        )
+        .optional()?
+        .ok_or_else(|| CommandError::not_found(format!("event run {course_id}")))?;
+
+    event_run_details.matches = get_event_run_matches(db, &course_id)?;
+
+    Ok(event_run_details)
+}
+
+#[tauri::command]
+pub(crate) async fn command_event_run_details(
+    course_id: String,
+    grouping: Option<DeckGrouping>,
+    db: State<'_, DatabaseState>,
+) -> Result<EventRunDetails, CommandError> {
+    let grouping = grouping.unwrap_or_default();
+    blocking::read(&db, move |db| {
+        get_event_run_details(db, course_id, grouping)
+    })
+    .await
+}
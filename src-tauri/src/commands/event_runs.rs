@@ -0,0 +1,55 @@
+use ap_core::match_insights::MatchInsightDB;
+use chrono::{DateTime, Utc};
+use indoc::indoc;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::commands::blocking;
+use crate::commands::error::CommandError;
+use crate::database::DatabaseState;
+use crate::match_format::MatchFilter;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct EventRun {
+    course_id: String,
+    event_name: String,
+    current_module: String,
+    wins: i32,
+    losses: i32,
+    created_at: DateTime<Utc>,
+}
+
+fn get_event_runs(db: &MatchInsightDB, filter: &MatchFilter) -> rusqlite::Result<Vec<EventRun>> {
+    let mut statement = db.conn.prepare(indoc! {r"
+        SELECT course_id, event_name, current_module, wins, losses, created_at
+        FROM event_runs
+        ORDER BY created_at DESC
+    "})?;
+    let event_runs = statement.query_map([], |row| {
+        Ok(EventRun {
+            course_id: row.get(0)?,
+            event_name: row.get(1)?,
+            current_module: row.get(2)?,
+            wins: row.get(3)?,
+            losses: row.get(4)?,
+            created_at: row.get(5)?,
+        })
+    })?;
+    let mut accepted = Vec::new();
+    for event_run in event_runs {
+        let event_run = event_run?;
+        if filter.accepts_event(&event_run.event_name) {
+            accepted.push(event_run);
+        }
+    }
+    Ok(accepted)
+}
+
+#[tauri::command]
+pub(crate) async fn command_event_runs(
+    filter: Option<MatchFilter>,
+    db: State<'_, DatabaseState>,
+) -> Result<Vec<EventRun>, CommandError> {
+    let filter = filter.unwrap_or_default();
+    blocking::read(&db, move |db| Ok(get_event_runs(db, &filter)?)).await
+}
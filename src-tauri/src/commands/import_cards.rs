@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tauri::{async_runtime, AppHandle, Manager, State};
+use tracing::{error, info};
+
+use crate::card_import::{self, OverlayCard};
+use crate::card_search::{CardIndex, CardIndexState};
+use crate::commands::error::CommandError;
+use crate::database::{recover, Database, DatabaseState};
+use crate::search;
+use crate::unknown_cards::{self, ResolveReport};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct CardImportReport {
+    imported: usize,
+    /// Ids the card database couldn't resolve before the import.
+    newly_resolved: usize,
+    /// Stored matches re-checked against the updated database.
+    unknown_cards: ResolveReport,
+    errors: Vec<String>,
+}
+
+fn import_cards(
+    scryfall_bulk_path: Option<String>,
+    mtga_database_path: Option<String>,
+    app: &AppHandle,
+    db: &Database,
+) -> Result<CardImportReport, CommandError> {
+    let mut report = CardImportReport::default();
+    let mut imported: Vec<OverlayCard> = Vec::new();
+    if let Some(path) = mtga_database_path {
+        match card_import::from_mtga_database(Path::new(&path)) {
+            Ok(cards) => imported.extend(cards),
+            Err(e) => report.errors.push(format!("{path}: {e}")),
+        }
+    }
+    if let Some(path) = scryfall_bulk_path {
+        match card_import::from_scryfall_bulk(Path::new(&path)) {
+            Ok(cards) => imported.extend(cards),
+            Err(e) => report.errors.push(format!("{path}: {e}")),
+        }
+    }
+    report.imported = imported.len();
+    if imported.is_empty() {
+        if !report.errors.is_empty() {
+            return Err(CommandError::CardDatabase(report.errors.join("; ")));
+        }
+        return Ok(report);
+    }
+
+    // the rest of the overlay is in the database already, from startup or an earlier import
+    {
+        let mut cards = db.cards_mut();
+        report.newly_resolved = card_import::apply(&mut cards, &imported)
+            .map_err(|e| CommandError::CardDatabase(e.to_string()))?;
+        if let Some(card_index) = app.try_state::<CardIndexState>() {
+            *recover(card_index.lock(), "card index") = CardIndex::new(&cards);
+        }
+    }
+
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| CommandError::CardDatabase(e.to_string()))?;
+    let overlay_path = card_import::overlay_path(&app_data_dir);
+    let mut overlay = card_import::read_overlay(&overlay_path).unwrap_or_else(|e| {
+        error!("Error reading card overlay, starting over: {}", e);
+        HashMap::default()
+    });
+    for card in imported {
+        overlay.insert(card.id.to_string(), card);
+    }
+    if let Err(e) = card_import::write_overlay(&overlay_path, &overlay) {
+        error!("Error writing card overlay: {}", e);
+        report.errors.push(e.to_string());
+    }
+
+    {
+        match unknown_cards::reconcile(db) {
+            Ok(resolve_report) => report.unknown_cards = resolve_report,
+            Err(e) => {
+                error!("Error resolving unknown cards: {}", e);
+                report.errors.push(e.to_string());
+            }
+        }
+        let mut db = db.write();
+        // Card names are indexed for search, ids that resolve now become searchable by name.
+        if report.newly_resolved > 0 {
+            if let Err(e) = search::rebuild(&mut db) {
+                error!("Error rebuilding the search index: {}", e);
+                report.errors.push(e.to_string());
+            }
+        }
+    }
+    info!(
+        "Imported {} cards, {} newly resolved",
+        report.imported, report.newly_resolved
+    );
+    Ok(report)
+}
+
+/// Imports cards from a Scryfall bulk data file and/or the client's card `SQLite` file. Scryfall
+/// wins where both describe the same id since it has images and oracle text.
+#[tauri::command]
+pub(crate) async fn command_import_cards(
+    scryfall_bulk_path: Option<String>,
+    mtga_database_path: Option<String>,
+    app: AppHandle,
+    db: State<'_, DatabaseState>,
+) -> Result<CardImportReport, CommandError> {
+    let db = db.inner().clone();
+    async_runtime::spawn_blocking(move || {
+        import_cards(scryfall_bulk_path, mtga_database_path, &app, &db)
+    })
+    .await?
+    .inspect_err(|e| error!("Error importing cards: {}", e))
+}
@@ -0,0 +1,38 @@
+use ap_core::models::deck::Deck;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::commands::blocking;
+use crate::commands::error::CommandError;
+use crate::database::DatabaseState;
+use crate::deck::{DeckDisplayRecord, DeckGrouping};
+use crate::decklist::parse_decklist;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct ImportedDecklist {
+    deck: Option<Deck>,
+    decklist: Option<DeckDisplayRecord>,
+    unresolved: Vec<String>,
+}
+
+#[tauri::command]
+pub(crate) async fn command_import_decklist(
+    text: String,
+    grouping: Option<DeckGrouping>,
+    db: State<'_, DatabaseState>,
+) -> Result<ImportedDecklist, CommandError> {
+    let grouping = grouping.unwrap_or_default();
+    blocking::read(&db, move |db| {
+        let imported = parse_decklist(&text, &db.cards);
+        Ok(ImportedDecklist {
+            decklist: Some(DeckDisplayRecord::from_decklist(
+                &imported.deck,
+                &db.cards,
+                grouping,
+            )),
+            deck: Some(imported.deck),
+            unresolved: imported.unresolved,
+        })
+    })
+    .await
+}
@@ -0,0 +1,199 @@
+use std::collections::BTreeSet;
+
+use chrono::{DateTime, Utc};
+use indoc::indoc;
+use serde::Deserialize;
+use tauri::State;
+
+use crate::annotations;
+use crate::commands::blocking;
+use crate::commands::error::CommandError;
+use crate::database::{DatabaseState, WriteDb};
+use crate::match_format::{MatchFilter, MatchFormat};
+use crate::match_trash::{self, TrashBatch};
+use crate::search;
+
+/// Which matches a bulk action applies to, every restriction given must hold.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub(crate) struct MatchSelection {
+    #[serde(flatten)]
+    filter: MatchFilter,
+    /// Case-insensitive substring of the opponent's name.
+    opponent: Option<String>,
+    played_after: Option<DateTime<Utc>>,
+    played_before: Option<DateTime<Utc>>,
+    /// Matches with or without a recorded result, the latter left behind by a client that
+    /// closed mid match.
+    complete: Option<bool>,
+    hidden: Option<bool>,
+}
+
+struct SelectableMatch {
+    id: String,
+    opponent: String,
+    created_at: DateTime<Utc>,
+    match_format: Option<MatchFormat>,
+    complete: bool,
+    hidden: bool,
+    tags: Option<BTreeSet<String>>,
+}
+
+impl MatchSelection {
+    fn accepts(&self, candidate: &SelectableMatch) -> bool {
+        self.filter.accepts(candidate.match_format.as_ref())
+            && self.filter.accepts_tags(candidate.tags.as_ref())
+            && self.opponent.as_ref().map_or(true, |opponent| {
+                candidate
+                    .opponent
+                    .to_lowercase()
+                    .contains(&opponent.to_lowercase())
+            })
+            && self
+                .played_after
+                .map_or(true, |after| candidate.created_at >= after)
+            && self
+                .played_before
+                .map_or(true, |before| candidate.created_at < before)
+            && self
+                .complete
+                .map_or(true, |complete| candidate.complete == complete)
+            && self
+                .hidden
+                .map_or(true, |hidden| candidate.hidden == hidden)
+    }
+}
+
+fn select_matches(
+    conn: &rusqlite::Connection,
+    selection: &MatchSelection,
+) -> rusqlite::Result<Vec<String>> {
+    let mut statement = conn.prepare(indoc! {r#"
+        SELECT
+            m.id,
+            m.opponent_player_name,
+            m.created_at,
+            mf.event_id,
+            mf.format,
+            mf.best_of,
+            EXISTS (
+                SELECT 1 FROM match_results mr
+                WHERE mr.match_id = m.id AND mr.result_scope = "MatchScope_Match"
+            ),
+            hm.match_id IS NOT NULL
+        FROM matches m
+        LEFT JOIN match_formats mf ON m.id = mf.match_id
+        LEFT JOIN hidden_matches hm ON m.id = hm.match_id
+        ORDER BY m.created_at DESC
+    "#})?;
+    let mut tags = annotations::tags_by_match(conn)?;
+    let candidates = statement.query_map([], |row| {
+        let event_id: Option<String> = row.get(3)?;
+        Ok(SelectableMatch {
+            id: row.get(0)?,
+            opponent: row.get(1)?,
+            created_at: row.get(2)?,
+            match_format: match event_id {
+                Some(event_id) => Some(MatchFormat {
+                    event_id,
+                    format: row.get(4)?,
+                    best_of: row.get(5)?,
+                }),
+                None => None,
+            },
+            complete: row.get(6)?,
+            hidden: row.get(7)?,
+            tags: None,
+        })
+    })?;
+
+    let mut match_ids = Vec::new();
+    for candidate in candidates {
+        let mut candidate = candidate?;
+        candidate.tags = tags.remove(&candidate.id);
+        if selection.accepts(&candidate) {
+            match_ids.push(candidate.id);
+        }
+    }
+    Ok(match_ids)
+}
+
+/// Brings the search index in line with matches that were just trashed or restored.
+fn reindex(db: &mut WriteDb<'_>, match_ids: &[String]) -> anyhow::Result<()> {
+    for match_id in match_ids {
+        search::index_match(db, match_id)?;
+    }
+    Ok(())
+}
+
+/// Ids of the matches `selection` picks out, newest first, to feed the bulk commands below.
+#[tauri::command]
+pub(crate) async fn command_select_matches(
+    selection: MatchSelection,
+    db: State<'_, DatabaseState>,
+) -> Result<Vec<String>, CommandError> {
+    blocking::read(&db, move |db| Ok(select_matches(&db.conn, &selection)?)).await
+}
+
+/// Moves matches to the trash, where they can be restored from until purged.
+#[tauri::command]
+pub(crate) async fn command_delete_matches(
+    match_ids: Vec<String>,
+    db: State<'_, DatabaseState>,
+) -> Result<TrashBatch, CommandError> {
+    blocking::write(&db, move |db| {
+        let batch = match_trash::delete(&mut db.conn, &match_ids)?;
+        reindex(db, &batch.match_ids)?;
+        Ok(batch)
+    })
+    .await
+}
+
+/// Folds duplicate records of one match, e.g. from ingesting a log twice, into `keep_match_id`.
+#[tauri::command]
+pub(crate) async fn command_merge_matches(
+    keep_match_id: String,
+    duplicate_match_ids: Vec<String>,
+    db: State<'_, DatabaseState>,
+) -> Result<TrashBatch, CommandError> {
+    blocking::write(&db, move |db| {
+        let batch = match_trash::merge(&mut db.conn, &keep_match_id, &duplicate_match_ids)?;
+        search::index_match(db, &keep_match_id)?;
+        reindex(db, &batch.match_ids)?;
+        Ok(batch)
+    })
+    .await
+}
+
+/// Hidden matches stay listed but are left out of the statistics commands.
+#[tauri::command]
+pub(crate) async fn command_hide_matches(
+    match_ids: Vec<String>,
+    hidden: bool,
+    db: State<'_, DatabaseState>,
+) -> Result<usize, CommandError> {
+    blocking::write(&db, move |db| {
+        Ok(match_trash::set_hidden(&mut db.conn, &match_ids, hidden)?)
+    })
+    .await
+}
+
+#[tauri::command]
+pub(crate) async fn command_trash(
+    db: State<'_, DatabaseState>,
+) -> Result<Vec<TrashBatch>, CommandError> {
+    blocking::read(&db, |db| Ok(match_trash::list(&db.conn)?)).await
+}
+
+/// Undoes a delete or merge, returning the restored match ids.
+#[tauri::command]
+pub(crate) async fn command_restore_trash(
+    batch_id: i64,
+    db: State<'_, DatabaseState>,
+) -> Result<Vec<String>, CommandError> {
+    blocking::write(&db, move |db| {
+        let match_ids = match_trash::restore(&mut db.conn, batch_id)?;
+        reindex(db, &match_ids)?;
+        Ok(match_ids)
+    })
+    .await
+}
@@ -1,18 +1,20 @@
-use std::sync::{Arc, Mutex};
-
-use ap_core::cards::CardsDatabase;
-use ap_core::match_insights::MatchInsightDB;
 use ap_core::models::deck::Deck;
 use ap_core::models::match_result::MatchResult;
 use ap_core::models::mulligan::MulliganInfo;
 use chrono::{DateTime, Utc};
 use indoc::indoc;
+use rusqlite::OptionalExtension;
 use serde::{Deserialize, Serialize};
 use tauri::State;
-use tracing::{error, info};
+use tracing::info;
 
 use crate::card::Card;
-use crate::deck::{DeckDifference, DeckDisplayRecord};
+use crate::cards::Cards;
+use crate::commands::blocking;
+use crate::commands::error::CommandError;
+use crate::database::{DatabaseState, ReadDb};
+use crate::deck::{deck_id, DeckDifference, DeckDisplayRecord, DeckGrouping};
+use crate::match_format::MatchFormat;
 
 #[derive(Debug, Deserialize, Serialize, Default, Clone)]
 struct Mulligan {
@@ -32,16 +34,12 @@ impl Mulligan {
         number_to_keep: i32,
         play_draw: String,
         decision: String,
-        cards_database: &CardsDatabase,
+        cards: &Cards,
     ) -> Self {
         let hand = hand
             .split(',')
             .filter_map(|card_id_str| card_id_str.parse::<i32>().ok())
-            .map(|card_id| -> Card {
-                cards_database
-                    .get(&card_id)
-                    .map_or_else(|| Card::new(card_id.to_string()), std::convert::Into::into)
-            })
+            .map(|card_id| -> Card { cards.card(card_id) })
             .collect();
 
         Self {
@@ -54,10 +52,7 @@ impl Mulligan {
         }
     }
 
-    pub fn from_mulligan_info(
-        mulligan_info: &MulliganInfo,
-        cards_database: &CardsDatabase,
-    ) -> Self {
+    pub fn from_mulligan_info(mulligan_info: &MulliganInfo, cards: &Cards) -> Self {
         Self::new(
             &mulligan_info.hand,
             mulligan_info.opponent_identity.clone(),
@@ -65,7 +60,7 @@ impl Mulligan {
             mulligan_info.number_to_keep,
             mulligan_info.play_draw.clone(),
             mulligan_info.decision.clone(),
-            cards_database,
+            cards,
         )
     }
 }
@@ -103,6 +98,8 @@ pub(crate) struct MatchDetails {
     controller_player_name: String,
     opponent_player_name: String,
     created_at: DateTime<Utc>,
+    match_format: Option<MatchFormat>,
+    deck_id: Option<String>,
     primary_decklist: Option<DeckDisplayRecord>,
     differences: Option<Vec<DeckDifference>>,
     game_results: Vec<GameResultDisplay>,
@@ -110,18 +107,11 @@ pub(crate) struct MatchDetails {
     mulligans: Vec<Mulligan>,
 }
 
-#[tauri::command]
-pub(crate) fn command_match_details(
-    match_id: String,
-    db: State<'_, Arc<Mutex<MatchInsightDB>>>,
-) -> MatchDetails {
-    let db_lock_result = db.inner().lock();
-    if let Err(e) = db_lock_result {
-        error!("Failed to obtain db lock: {}", e);
-        return MatchDetails::default();
-    }
-    let mut db = db_lock_result.expect("handled error case");
-
+fn get_match_details(
+    db: &mut ReadDb<'_>,
+    match_id: &str,
+    grouping: DeckGrouping,
+) -> Result<MatchDetails, CommandError> {
     let mut match_details = {
         let mut statement = db.conn.prepare(indoc! {r#"
             SELECT
@@ -129,11 +119,11 @@ pub(crate) fn command_match_details(
             FROM matches m JOIN match_results mr ON m.id = mr.match_id
             WHERE m.id = ?1 AND mr.result_scope = "MatchScope_Match" LIMIT 1
             "#}
-        ).expect("valid sql");
+        )?;
 
         info!("Getting match details for match_id: {}", match_id);
         statement
-            .query_row([&match_id], |row| {
+            .query_row([match_id], |row| {
                 let id: String = row.get(0)?;
                 let controller_player_name: String = row.get(1)?;
                 let opponent_player_name: String = row.get(2)?;
@@ -147,6 +137,8 @@ pub(crate) fn command_match_details(
                     controller_player_name,
                     opponent_player_name,
                     created_at,
+                    match_format: None,
+                    deck_id: None,
                     primary_decklist: None,
                     differences: None,
                     game_results: Vec::new(),
@@ -154,21 +146,23 @@ pub(crate) fn command_match_details(
                     mulligans: Vec::new(),
                 })
             })
-            .unwrap_or_else(|e| {
-                error!("Error getting match details: {:?}", e);
-                MatchDetails::default()
-            })
+            .optional()?
+            .ok_or_else(|| CommandError::not_found(format!("match {match_id}")))?
     };
 
-    match_details.decklists = db.get_decklists(&match_id).unwrap_or_default();
+    match_details.match_format = MatchFormat::get(&db.conn, match_id)?;
+
+    match_details.decklists = db.get_decklists(match_id)?;
+
+    match_details.deck_id = match_details.decklists.first().map(deck_id);
 
     match_details.primary_decklist = match_details.decklists.first().map(|primary_decklist| {
-        DeckDisplayRecord::from_decklist(primary_decklist, &db.cards_database)
+        DeckDisplayRecord::from_decklist(primary_decklist, &db.cards, grouping)
     });
 
     match_details.decklists.windows(2).for_each(|pair| {
         if let [prev, next] = pair {
-            let diff = DeckDifference::difference(prev, next, &db.cards_database);
+            let diff = DeckDifference::difference(prev, next, &db.cards);
             match_details
                 .differences
                 .get_or_insert_with(Vec::new)
@@ -176,22 +170,15 @@ pub(crate) fn command_match_details(
         }
     });
 
-    let raw_mulligans = db.get_mulligans(&match_id).unwrap_or_else(|e| {
-        error!("Error retrieving Mulligans: {}", e);
-        Vec::default()
-    });
+    let raw_mulligans = db.get_mulligans(match_id)?;
 
     match_details.mulligans = raw_mulligans
         .iter()
-        .map(|mulligan| Mulligan::from_mulligan_info(mulligan, &db.cards_database))
+        .map(|mulligan| Mulligan::from_mulligan_info(mulligan, &db.cards))
         .collect();
 
     match_details.game_results = db
-        .get_match_results(&match_id)
-        .unwrap_or_else(|e| {
-            error!("Error retrieving game results: {}", e);
-            Vec::default()
-        })
+        .get_match_results(match_id)?
         .iter()
         .map(|mr| {
             GameResultDisplay::from_match_result(
@@ -203,5 +190,15 @@ pub(crate) fn command_match_details(
         })
         .collect();
 
-    match_details
+    Ok(match_details)
+}
+
+#[tauri::command]
+pub(crate) async fn command_match_details(
+    match_id: String,
+    grouping: Option<DeckGrouping>,
+    db: State<'_, DatabaseState>,
+) -> Result<MatchDetails, CommandError> {
+    let grouping = grouping.unwrap_or_default();
+    blocking::read(&db, move |db| get_match_details(db, &match_id, grouping)).await
 }
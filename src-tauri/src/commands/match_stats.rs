@@ -0,0 +1,71 @@
+use std::collections::BTreeMap;
+
+use ap_core::match_insights::MatchInsightDB;
+use indoc::indoc;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::annotations;
+use crate::commands::blocking;
+use crate::commands::error::CommandError;
+use crate::database::DatabaseState;
+use crate::match_format::{MatchFilter, MatchFormat, MatchGrouping};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct MatchRecord {
+    group: String,
+    matches: u32,
+    wins: u32,
+    losses: u32,
+    win_rate: Option<f64>,
+}
+
+fn match_outcomes(db: &MatchInsightDB) -> rusqlite::Result<Vec<(String, bool)>> {
+    let mut statement = db.conn.prepare(indoc! {r#"
+        SELECT m.id, m.controller_seat_id = mr.winning_team_id
+        FROM matches m JOIN match_results mr ON m.id = mr.match_id
+        WHERE mr.result_scope = "MatchScope_Match"
+            AND m.id NOT IN (SELECT match_id FROM hidden_matches)
+    "#})?;
+    let outcomes = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    outcomes.collect()
+}
+
+fn get_match_stats(
+    db: &MatchInsightDB,
+    group_by: MatchGrouping,
+    filter: &MatchFilter,
+) -> rusqlite::Result<Vec<MatchRecord>> {
+    let match_formats = MatchFormat::get_all(&db.conn)?;
+    let tags = annotations::tags_by_match(&db.conn)?;
+    let mut records: BTreeMap<String, MatchRecord> = BTreeMap::new();
+    for (match_id, won) in match_outcomes(db)? {
+        let match_format = match_formats.get(&match_id);
+        if !filter.accepts(match_format) || !filter.accepts_tags(tags.get(&match_id)) {
+            continue;
+        }
+        let group = group_by.key(match_format);
+        let record = records.entry(group.clone()).or_insert_with(|| MatchRecord {
+            group,
+            ..Default::default()
+        });
+        record.matches += 1;
+        if won {
+            record.wins += 1;
+        } else {
+            record.losses += 1;
+        }
+        record.win_rate = Some(f64::from(record.wins) / f64::from(record.matches));
+    }
+    Ok(records.into_values().collect())
+}
+
+#[tauri::command]
+pub(crate) async fn command_match_stats(
+    group_by: MatchGrouping,
+    filter: Option<MatchFilter>,
+    db: State<'_, DatabaseState>,
+) -> Result<Vec<MatchRecord>, CommandError> {
+    let filter = filter.unwrap_or_default();
+    blocking::read(&db, move |db| Ok(get_match_stats(db, group_by, &filter)?)).await
+}
@@ -1,18 +1,51 @@
-use ap_core::match_insights::MatchInsightDB;
+use std::collections::BTreeSet;
+
 use ap_core::models::mtga_match::MTGAMatch;
-use std::sync::{Arc, Mutex};
+use serde::Serialize;
 use tauri::State;
-use tracing::error;
+
+use crate::annotations;
+use crate::commands::blocking;
+use crate::commands::error::CommandError;
+use crate::database::DatabaseState;
+use crate::match_format::{MatchFilter, MatchFormat};
+use crate::match_trash;
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct MatchSummary {
+    #[serde(flatten)]
+    mtga_match: MTGAMatch,
+    match_format: Option<MatchFormat>,
+    /// Left out of the statistics commands.
+    hidden: bool,
+    tags: BTreeSet<String>,
+}
 
 #[tauri::command]
-pub fn command_matches(db: State<'_, Arc<Mutex<MatchInsightDB>>>) -> Vec<MTGAMatch> {
-    let mut db = db.inner().lock().expect("Failed to lock db");
-    db.get_matches()
-        .unwrap_or_else(|e| {
-            error!("error retrieving matches {}", e);
-            Vec::default()
-        })
-        .into_iter()
-        .rev()
-        .collect()
+pub(crate) async fn command_matches(
+    filter: Option<MatchFilter>,
+    db: State<'_, DatabaseState>,
+) -> Result<Vec<MatchSummary>, CommandError> {
+    let filter = filter.unwrap_or_default();
+    blocking::read(&db, move |db| {
+        let mut match_formats = MatchFormat::get_all(&db.conn)?;
+        let hidden = match_trash::hidden_match_ids(&db.conn)?;
+        let mut tags = annotations::tags_by_match(&db.conn)?;
+        Ok(db
+            .get_matches()?
+            .into_iter()
+            .rev()
+            .map(|mtga_match| MatchSummary {
+                match_format: match_formats.remove(&mtga_match.id),
+                hidden: hidden.contains(&mtga_match.id),
+                tags: tags.remove(&mtga_match.id).unwrap_or_default(),
+                mtga_match,
+            })
+            .filter(|summary| {
+                filter.accepts(summary.match_format.as_ref())
+                    && filter.accepts_tags(Some(&summary.tags))
+            })
+            .collect())
+    })
+    .await
 }
@@ -1,2 +1,23 @@
+pub(crate) mod annotations;
+pub(crate) mod backups;
+pub(crate) mod blocking;
+pub(crate) mod card_stats;
+pub(crate) mod collection;
+pub(crate) mod current_match;
+pub(crate) mod deck_diff;
+pub(crate) mod draft_details;
+pub(crate) mod drafts;
+pub(crate) mod error;
+pub(crate) mod event_run_details;
+pub(crate) mod event_runs;
+pub(crate) mod import_cards;
+pub(crate) mod import_decklist;
+pub(crate) mod manage_matches;
 pub(crate) mod match_details;
+pub(crate) mod match_stats;
 pub(crate) mod matches;
+pub(crate) mod rank_history;
+pub(crate) mod search;
+pub(crate) mod search_cards;
+pub(crate) mod unknown_cards;
+pub(crate) mod wildcard_cost;
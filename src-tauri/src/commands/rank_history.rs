@@ -0,0 +1,97 @@
+use ap_core::match_insights::MatchInsightDB;
+use chrono::{DateTime, Utc};
+use indoc::indoc;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::commands::blocking;
+use crate::commands::error::CommandError;
+use crate::database::DatabaseState;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RankSnapshot {
+    created_at: DateTime<Utc>,
+    match_id: Option<String>,
+    ranked_format: String,
+    season_ordinal: i32,
+    rank_class: String,
+    level: i32,
+    step: i32,
+    percentile: Option<f64>,
+    leaderboard_place: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Season {
+    ordinal: i32,
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct RankHistory {
+    snapshots: Vec<RankSnapshot>,
+    seasons: Vec<Season>,
+}
+
+fn get_snapshots(
+    db: &MatchInsightDB,
+    ranked_format: Option<&str>,
+) -> rusqlite::Result<Vec<RankSnapshot>> {
+    let mut statement = db.conn.prepare(indoc! {r"
+        SELECT created_at, match_id, ranked_format, season_ordinal, rank_class, level, step,
+            percentile, leaderboard_place
+        FROM rank_snapshots
+        WHERE ?1 IS NULL OR ranked_format = ?1
+        ORDER BY created_at
+    "})?;
+    let snapshots = statement.query_map([ranked_format], |row| {
+        Ok(RankSnapshot {
+            created_at: row.get(0)?,
+            match_id: row.get(1)?,
+            ranked_format: row.get(2)?,
+            season_ordinal: row.get(3)?,
+            rank_class: row.get(4)?,
+            level: row.get(5)?,
+            step: row.get(6)?,
+            percentile: row.get(7)?,
+            leaderboard_place: row.get(8)?,
+        })
+    })?;
+    snapshots.collect()
+}
+
+/// Seasons reported by the client, falling back to the span of our own snapshots for seasons
+/// the client never described.
+fn get_seasons(db: &MatchInsightDB) -> rusqlite::Result<Vec<Season>> {
+    let mut statement = db.conn.prepare(indoc! {r"
+        SELECT season_ordinal, start_time, end_time FROM rank_seasons
+        UNION ALL
+        SELECT season_ordinal, MIN(created_at), MAX(created_at) FROM rank_snapshots
+        WHERE season_ordinal NOT IN (SELECT season_ordinal FROM rank_seasons)
+        GROUP BY season_ordinal
+        ORDER BY season_ordinal
+    "})?;
+    let seasons = statement.query_map([], |row| {
+        Ok(Season {
+            ordinal: row.get(0)?,
+            start_time: row.get(1)?,
+            end_time: row.get(2)?,
+        })
+    })?;
+    seasons.collect()
+}
+
+#[tauri::command]
+pub(crate) async fn command_rank_history(
+    ranked_format: Option<String>,
+    db: State<'_, DatabaseState>,
+) -> Result<RankHistory, CommandError> {
+    blocking::read(&db, move |db| {
+        Ok(RankHistory {
+            snapshots: get_snapshots(db, ranked_format.as_deref())?,
+            seasons: get_seasons(db)?,
+        })
+    })
+    .await
+}
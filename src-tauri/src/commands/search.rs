@@ -0,0 +1,19 @@
+use tauri::State;
+
+use crate::commands::blocking;
+use crate::commands::error::CommandError;
+use crate::database::DatabaseState;
+use crate::search::{self, SearchHit};
+
+const DEFAULT_LIMIT: u32 = 50;
+
+/// Matches whose notes, opponent, event or cards played match `query`, best first.
+#[tauri::command]
+pub(crate) async fn command_search(
+    query: String,
+    limit: Option<u32>,
+    db: State<'_, DatabaseState>,
+) -> Result<Vec<SearchHit>, CommandError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+    blocking::read(&db, move |db| Ok(search::search(&db.conn, &query, limit)?)).await
+}
@@ -0,0 +1,25 @@
+use tauri::{async_runtime, State};
+
+use crate::card_search::{CardIndexState, CardSearchFilter, CardSearchResult};
+use crate::commands::error::CommandError;
+use crate::database::recover;
+
+const DEFAULT_LIMIT: usize = 50;
+
+#[tauri::command]
+pub(crate) async fn command_search_cards(
+    query: String,
+    filter: Option<CardSearchFilter>,
+    limit: Option<usize>,
+    index: State<'_, CardIndexState>,
+) -> Result<Vec<CardSearchResult>, CommandError> {
+    let index = index.inner().clone();
+    async_runtime::spawn_blocking(move || {
+        Ok(recover(index.lock(), "card index").search(
+            &query,
+            &filter.unwrap_or_default(),
+            limit.unwrap_or(DEFAULT_LIMIT),
+        ))
+    })
+    .await?
+}
@@ -0,0 +1,84 @@
+use std::collections::BTreeMap;
+
+use ap_core::match_insights::MatchInsightDB;
+use chrono::{DateTime, Utc};
+use indoc::indoc;
+use serde::{Deserialize, Serialize};
+use tauri::{async_runtime, State};
+use tracing::error;
+
+use crate::commands::blocking;
+use crate::commands::error::CommandError;
+use crate::database::DatabaseState;
+use crate::unknown_cards::{self, ResolveReport};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct UnknownCard {
+    card_id: i32,
+    match_ids: Vec<String>,
+    draft_ids: Vec<String>,
+    course_ids: Vec<String>,
+    in_collection: bool,
+    first_seen: DateTime<Utc>,
+}
+
+impl UnknownCard {
+    fn new(card_id: i32, first_seen: DateTime<Utc>) -> Self {
+        Self {
+            card_id,
+            match_ids: Vec::new(),
+            draft_ids: Vec::new(),
+            course_ids: Vec::new(),
+            in_collection: false,
+            first_seen,
+        }
+    }
+}
+
+fn get_unknown_cards(db: &MatchInsightDB) -> rusqlite::Result<Vec<UnknownCard>> {
+    let mut statement = db.conn.prepare(indoc! {r"
+        SELECT card_id, 'match', match_id, created_at FROM unknown_cards
+        UNION ALL
+        SELECT card_id, source, source_id, created_at FROM unknown_pool_cards
+        ORDER BY card_id, created_at
+    "})?;
+    let rows = statement.query_map([], |row| {
+        Ok((
+            row.get::<_, i32>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, DateTime<Utc>>(3)?,
+        ))
+    })?;
+    let mut unknown_cards: BTreeMap<i32, UnknownCard> = BTreeMap::new();
+    for row in rows {
+        let (card_id, source, source_id, created_at) = row?;
+        let unknown_card = unknown_cards
+            .entry(card_id)
+            .or_insert_with(|| UnknownCard::new(card_id, created_at));
+        match source.as_str() {
+            "match" => unknown_card.match_ids.push(source_id),
+            "draft" => unknown_card.draft_ids.push(source_id),
+            "event_run" => unknown_card.course_ids.push(source_id),
+            _ => unknown_card.in_collection = true,
+        }
+    }
+    Ok(unknown_cards.into_values().collect())
+}
+
+#[tauri::command]
+pub(crate) async fn command_unknown_cards(
+    db: State<'_, DatabaseState>,
+) -> Result<Vec<UnknownCard>, CommandError> {
+    blocking::read(&db, |db| Ok(get_unknown_cards(db)?)).await
+}
+
+#[tauri::command]
+pub(crate) async fn command_resolve_unknown_cards(
+    db: State<'_, DatabaseState>,
+) -> Result<ResolveReport, CommandError> {
+    let db = db.inner().clone();
+    async_runtime::spawn_blocking(move || Ok(unknown_cards::reconcile(&db)?))
+        .await?
+        .inspect_err(|e| error!("Command failed: {}", e))
+}
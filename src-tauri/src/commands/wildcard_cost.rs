@@ -0,0 +1,109 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::card::{Card, Rarity, TypeLine};
+use crate::collection;
+use crate::commands::blocking;
+use crate::commands::error::CommandError;
+use crate::database::{DatabaseState, ReadDb};
+use crate::decklist::{entry_name, DeckSource};
+
+/// Arena never asks for more than a playset, extra copies turn into vault progress.
+const PLAYSET: u16 = 4;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RarityCost {
+    rarity: Rarity,
+    missing: Vec<Card>,
+    wildcards_needed: u16,
+    wildcards_owned: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct WildcardCost {
+    rarities: Vec<RarityCost>,
+    total_wildcards_needed: u16,
+    unresolved: Vec<String>,
+}
+
+fn get_wildcard_cost(db: &mut ReadDb<'_>, source: &DeckSource) -> anyhow::Result<WildcardCost> {
+    let imported = source.resolve(db)?;
+    let cards = &db.cards;
+
+    let mut needed: HashMap<String, (i32, u16)> = HashMap::default();
+    let deck_quantities = imported.deck.quantities();
+    let sideboard_quantities = imported.deck.sideboard_quantities();
+    for (card_id, quantity) in deck_quantities.iter().chain(sideboard_quantities.iter()) {
+        let Some(entry) = cards.get(*card_id) else {
+            continue;
+        };
+        if TypeLine::parse(&entry.type_line).has_supertype("Basic") {
+            continue;
+        }
+        let (_, needed_quantity) = needed
+            .entry(entry_name(entry).to_string())
+            .or_insert((*card_id, 0));
+        *needed_quantity = (*needed_quantity + quantity).min(PLAYSET);
+    }
+
+    let (owned_cards, inventory) = match collection::latest_snapshot_id(&db.conn)? {
+        Some(snapshot_id) => (
+            collection::snapshot_cards(&db.conn, snapshot_id)?,
+            collection::snapshot_inventory(&db.conn, snapshot_id)?.unwrap_or_default(),
+        ),
+        None => (HashMap::default(), collection::Inventory::default()),
+    };
+    let mut owned: HashMap<&str, u16> = HashMap::default();
+    for (card_id, quantity) in &owned_cards {
+        if let Some(entry) = cards.get(*card_id) {
+            *owned.entry(entry_name(entry)).or_default() += quantity;
+        }
+    }
+
+    let mut missing_by_rarity: BTreeMap<Rarity, Vec<Card>> = BTreeMap::default();
+    for (name, (card_id, needed_quantity)) in &needed {
+        let owned_quantity = owned.get(name.as_str()).copied().unwrap_or_default();
+        if owned_quantity >= *needed_quantity {
+            continue;
+        }
+        let mut card: Card = cards.card(*card_id);
+        card.quantity = needed_quantity - owned_quantity;
+        missing_by_rarity.entry(card.rarity).or_default().push(card);
+    }
+
+    let rarities: Vec<RarityCost> = missing_by_rarity
+        .into_iter()
+        .rev()
+        .map(|(rarity, mut missing)| {
+            missing.sort();
+            RarityCost {
+                rarity,
+                wildcards_needed: missing.iter().map(|card| card.quantity).sum(),
+                wildcards_owned: match rarity {
+                    Rarity::Common => inventory.wildcard_common,
+                    Rarity::Uncommon => inventory.wildcard_uncommon,
+                    Rarity::Rare => inventory.wildcard_rare,
+                    Rarity::Mythic => inventory.wildcard_mythic,
+                    Rarity::Unknown => 0,
+                },
+                missing,
+            }
+        })
+        .collect();
+
+    Ok(WildcardCost {
+        total_wildcards_needed: rarities.iter().map(|cost| cost.wildcards_needed).sum(),
+        rarities,
+        unresolved: imported.unresolved,
+    })
+}
+
+#[tauri::command]
+pub(crate) async fn command_wildcard_cost(
+    source: DeckSource,
+    db: State<'_, DatabaseState>,
+) -> Result<WildcardCost, CommandError> {
+    blocking::read(&db, move |db| Ok(get_wildcard_cost(db, &source)?)).await
+}
@@ -0,0 +1,116 @@
+use std::sync::{Arc, Mutex};
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::card::Card;
+use crate::cards::Cards;
+use crate::game_state::GameStateTracker;
+
+pub(crate) type CurrentMatchState = Arc<Mutex<Option<CurrentMatch>>>;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct PlayerSnapshot {
+    pub seat_id: i64,
+    pub name: String,
+    pub life_total: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct ZoneSnapshot {
+    pub zone_id: i64,
+    pub zone_type: String,
+    pub owner_seat_id: i64,
+    pub size: usize,
+    /// Only the cards the controller can see, hidden objects just count towards `size`.
+    pub cards: Vec<Card>,
+}
+
+/// Snapshot of the match being played right now, rebuilt as log events stream in.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct CurrentMatch {
+    pub match_id: String,
+    pub event_id: String,
+    pub game_number: i32,
+    pub controller_seat_id: i64,
+    pub controller_player_name: String,
+    pub opponent_player_name: String,
+    pub deck: Vec<Card>,
+    pub turn_number: i64,
+    pub active_seat_id: i64,
+    pub phase: String,
+    pub step: String,
+    pub players: Vec<PlayerSnapshot>,
+    pub zones: Vec<ZoneSnapshot>,
+}
+
+fn resolve(card_id: i32, cards: &Cards) -> Card {
+    cards.card(card_id)
+}
+
+impl CurrentMatch {
+    pub fn from_game_state(game_state: &GameStateTracker, cards: &Cards) -> Self {
+        let controller_seat_id = game_state.controller_seat_id();
+        let players = game_state.players();
+        let controller = players.iter().find(|p| p.seat_id == controller_seat_id);
+        let opponent = players.iter().find(|p| p.seat_id != controller_seat_id);
+
+        let deck = game_state
+            .deck()
+            .iter()
+            .counts()
+            .into_iter()
+            .map(|(card_id, quantity)| {
+                let mut card = resolve(*card_id, cards);
+                card.quantity = u16::try_from(quantity).unwrap_or(u16::MAX);
+                card
+            })
+            .sorted()
+            .collect();
+
+        let zones = game_state
+            .zones()
+            .sorted_by_key(|(zone_id, _)| **zone_id)
+            .map(|(zone_id, zone)| ZoneSnapshot {
+                zone_id: *zone_id,
+                zone_type: zone.kind.clone(),
+                owner_seat_id: zone.owner_seat_id,
+                size: zone.instance_ids.len(),
+                cards: zone
+                    .instance_ids
+                    .iter()
+                    .filter_map(|instance_id| game_state.object(*instance_id))
+                    .filter(|game_object| {
+                        game_object.visibility == "Visibility_Public"
+                            || game_object.owner_seat_id == controller_seat_id
+                    })
+                    .map(|game_object| resolve(game_object.grp_id, cards))
+                    .collect(),
+            })
+            .collect();
+
+        let turn_info = game_state.turn_info();
+        Self {
+            match_id: game_state.match_id().to_string(),
+            event_id: controller.map(|p| p.event_id.clone()).unwrap_or_default(),
+            game_number: game_state.game_number(),
+            controller_seat_id,
+            controller_player_name: controller.map(|p| p.name.clone()).unwrap_or_default(),
+            opponent_player_name: opponent.map(|p| p.name.clone()).unwrap_or_default(),
+            deck,
+            turn_number: turn_info.turn_number,
+            active_seat_id: turn_info.active_seat_id,
+            phase: turn_info.phase.clone(),
+            step: turn_info.step.clone(),
+            players: players
+                .iter()
+                .map(|p| PlayerSnapshot {
+                    seat_id: p.seat_id,
+                    name: p.name.clone(),
+                    life_total: p.life_total,
+                })
+                .collect(),
+            zones,
+        }
+    }
+}
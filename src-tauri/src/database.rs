@@ -0,0 +1,222 @@
+use std::ops::{Deref, DerefMut};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LockResult, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::Duration;
+
+use ap_core::match_insights::MatchInsightDB;
+use rusqlite::{Connection, OpenFlags};
+use tracing::{info, warn};
+
+use crate::cards::Cards;
+
+pub(crate) type DatabaseState = Arc<Database>;
+
+/// Readers beyond this many are closed when returned instead of kept for reuse.
+const MAX_IDLE_READERS: usize = 4;
+
+/// How long a connection waits on another's lock before `SQLITE_BUSY`, mostly checkpoints.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A panic while holding one of our locks leaves nothing half done worth refusing service over:
+/// an open `Transaction` rolls back as the thread unwinds and cards are only ever added.
+pub(crate) fn recover<T>(result: LockResult<T>, lock_name: &str) -> T {
+    result.unwrap_or_else(|e| {
+        warn!("Recovering poisoned {} lock", lock_name);
+        e.into_inner()
+    })
+}
+
+/// A connection with the copy of the cards `ap_core` reads through it, and the version of the
+/// cards it was copied at.
+struct PooledDb {
+    db: MatchInsightDB,
+    cards_version: u64,
+}
+
+impl PooledDb {
+    fn sync_cards(&mut self, cards: &Cards, cards_version: u64) {
+        if self.cards_version != cards_version {
+            self.db.cards_database = cards.copy_database();
+            self.cards_version = cards_version;
+        }
+    }
+}
+
+/// matches.db in WAL mode. Ingest and the few commands that change data share one writer
+/// connection, every other command borrows a read-only connection from a pool so a long query
+/// never holds up ingest or another command.
+pub(crate) struct Database {
+    path: PathBuf,
+    cards: RwLock<Cards>,
+    /// Bumped by every `cards_mut` under the write lock, connections holding an older copy of
+    /// the cards get a fresh one when next handed out.
+    cards_version: AtomicU64,
+    writer: Mutex<PooledDb>,
+    idle_readers: Mutex<Vec<PooledDb>>,
+}
+
+impl Database {
+    /// Takes over an initialized and migrated database, holding a copy of `cards`.
+    pub fn new(path: PathBuf, db: MatchInsightDB, cards: Cards) -> rusqlite::Result<Self> {
+        let journal_mode: String =
+            db.conn
+                .pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get(0))?;
+        info!("matches.db journal mode: {}", journal_mode);
+        db.conn.busy_timeout(BUSY_TIMEOUT)?;
+        Ok(Self {
+            path,
+            cards: RwLock::new(cards),
+            cards_version: AtomicU64::new(0),
+            writer: Mutex::new(PooledDb {
+                db,
+                cards_version: 0,
+            }),
+            idle_readers: Mutex::new(Vec::new()),
+        })
+    }
+
+    fn open_reader(&self, cards: &Cards) -> rusqlite::Result<MatchInsightDB> {
+        let conn = Connection::open_with_flags(
+            &self.path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        Ok(MatchInsightDB::new(conn, cards.copy_database()))
+    }
+
+    /// A read-only connection, which sees everything committed before it started a statement.
+    pub fn read(&self) -> rusqlite::Result<ReadDb<'_>> {
+        let cards = self.cards();
+        let cards_version = self.cards_version.load(Ordering::Relaxed);
+        let idle = recover(self.idle_readers.lock(), "reader pool").pop();
+        let mut db = match idle {
+            Some(db) => db,
+            None => PooledDb {
+                db: self.open_reader(&cards)?,
+                cards_version,
+            },
+        };
+        db.sync_cards(&cards, cards_version);
+        Ok(ReadDb {
+            pool: self,
+            db: Some(db),
+            cards,
+        })
+    }
+
+    /// The writer connection, held until the returned guard drops.
+    pub fn write(&self) -> WriteDb<'_> {
+        let mut db = recover(self.writer.lock(), "writer");
+        let cards = self.cards();
+        db.sync_cards(&cards, self.cards_version.load(Ordering::Relaxed));
+        WriteDb { db, cards }
+    }
+
+    pub fn cards(&self) -> RwLockReadGuard<'_, Cards> {
+        recover(self.cards.read(), "cards")
+    }
+
+    /// Don't call while holding a `ReadDb` or `WriteDb`, they keep the cards read locked.
+    pub fn cards_mut(&self) -> RwLockWriteGuard<'_, Cards> {
+        let cards = recover(self.cards.write(), "cards");
+        self.cards_version.fetch_add(1, Ordering::Relaxed);
+        cards
+    }
+}
+
+/// A pooled read-only connection, with the cards read locked for as long as it is held.
+pub(crate) struct ReadDb<'a> {
+    pool: &'a Database,
+    db: Option<PooledDb>,
+    pub cards: RwLockReadGuard<'a, Cards>,
+}
+
+impl Deref for ReadDb<'_> {
+    type Target = MatchInsightDB;
+
+    fn deref(&self) -> &Self::Target {
+        &self.db.as_ref().expect("only taken on drop").db
+    }
+}
+
+impl DerefMut for ReadDb<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.db.as_mut().expect("only taken on drop").db
+    }
+}
+
+impl Drop for ReadDb<'_> {
+    fn drop(&mut self) {
+        if let Some(db) = self.db.take() {
+            let mut idle_readers = recover(self.pool.idle_readers.lock(), "reader pool");
+            if idle_readers.len() < MAX_IDLE_READERS {
+                idle_readers.push(db);
+            }
+        }
+    }
+}
+
+/// The writer connection, with the cards read locked for as long as it is held.
+pub(crate) struct WriteDb<'a> {
+    db: MutexGuard<'a, PooledDb>,
+    pub cards: RwLockReadGuard<'a, Cards>,
+}
+
+impl Deref for WriteDb<'_> {
+    type Target = MatchInsightDB;
+
+    fn deref(&self) -> &Self::Target {
+        &self.db.db
+    }
+}
+
+impl DerefMut for WriteDb<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.db.db
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod testing {
+    use std::collections::HashMap;
+
+    use ap_core::cards::CardsDatabase;
+    use ap_core::match_insights::MatchInsightDB;
+    use chrono::Utc;
+    use indoc::indoc;
+    use rusqlite::Connection;
+
+    use crate::schema;
+
+    /// An in-memory matches.db with `ap_core`'s tables and every migration applied.
+    pub(crate) fn database() -> MatchInsightDB {
+        migrated(Connection::open_in_memory().expect("Could not open database"))
+    }
+
+    pub(crate) fn migrated(conn: Connection) -> MatchInsightDB {
+        let mut db = MatchInsightDB::new(
+            conn,
+            CardsDatabase {
+                db: HashMap::default(),
+            },
+        );
+        db.init().expect("Could not create ap_core's tables");
+        schema::migrate(&mut db.conn).expect("Could not migrate");
+        db
+    }
+
+    /// The `matches` row `ap_core` writes for a match against `opponent`.
+    pub(crate) fn insert_match(conn: &Connection, match_id: &str, opponent: &str) {
+        conn.execute(
+            indoc! {r"
+                INSERT INTO matches (
+                    id, controller_seat_id, controller_player_name, opponent_player_name, created_at
+                )
+                VALUES (?1, 1, 'me', ?2, ?3)
+            "},
+            (match_id, opponent, Utc::now()),
+        )
+        .expect("Could not insert match");
+    }
+}
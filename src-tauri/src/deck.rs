@@ -1,52 +1,80 @@
 use std::collections::HashMap;
 
-use ap_core::cards::CardsDatabase;
 use ap_core::models::deck::Deck;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
 use crate::card::{Card, CardType};
+use crate::cards::Cards;
+
+/// How `DeckDisplayRecord` files cards with several types.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub(crate) enum DeckGrouping {
+    /// Under their primary type only, so the main deck adds up to the deck's size.
+    #[default]
+    PrimaryType,
+    /// Under every type of every face, an artifact creature is listed twice.
+    EachType,
+}
+
+impl DeckGrouping {
+    pub fn group(self, cards: impl IntoIterator<Item = Card>) -> HashMap<CardType, Vec<Card>> {
+        let mut groups: HashMap<CardType, Vec<Card>> = HashMap::new();
+        for card in cards {
+            match self {
+                Self::PrimaryType => {
+                    groups
+                        .entry(card.type_line.primary_type())
+                        .or_default()
+                        .push(card);
+                }
+                Self::EachType => {
+                    for card_type in card.all_types() {
+                        groups.entry(card_type).or_default().push(card.clone());
+                    }
+                }
+            }
+        }
+        groups.values_mut().for_each(|cards| cards.sort());
+        groups
+    }
+}
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub(crate) struct DeckDisplayRecord {
     pub archetype: String,
     pub main_deck: HashMap<CardType, Vec<Card>>,
     pub sideboard: Vec<Card>,
+    /// Lands in the main deck, counting modal double-faced spells with a land face.
+    pub land_count: u16,
 }
 
 impl DeckDisplayRecord {
-    pub fn from_decklist(value: &Deck, cards_db: &CardsDatabase) -> Self {
+    pub fn from_decklist(value: &Deck, cards: &Cards, grouping: DeckGrouping) -> Self {
         let archetype = "Unknown".to_string();
 
         let main_quantities = value.quantities();
         let sideboard_quantities = value.sideboard_quantities();
 
-        let mut main_cards = main_quantities
+        let main_cards: Vec<Card> = main_quantities
             .keys()
             .map(|card_id| {
-                let mut card: Card = cards_db
-                    .get(&card_id)
-                    .map_or_else(|| Card::new(card_id.to_string()), std::convert::Into::into);
+                let mut card: Card = cards.card(*card_id);
                 card.quantity = *main_quantities.get(card_id).unwrap_or(&0u16);
                 card
             })
-            .fold(
-                HashMap::new(),
-                |mut acc: HashMap<CardType, Vec<Card>>, card| {
-                    let card_type = card.type_field.clone();
-                    acc.entry(card_type).or_default().push(card);
-                    acc
-                },
-            );
-        main_cards.values_mut().for_each(|cards| cards.sort());
+            .collect();
+        let land_count = main_cards
+            .iter()
+            .filter(|card| card.is_land())
+            .map(|card| card.quantity)
+            .sum();
 
         let sideboard_cards = sideboard_quantities
             .keys()
             .copied()
             .map(|card_id| {
-                let mut card: Card = cards_db
-                    .get(&card_id)
-                    .map_or_else(|| Card::new(card_id.to_string()), std::convert::Into::into);
+                let mut card: Card = cards.card(card_id);
                 card.quantity = *sideboard_quantities.get(&card_id).unwrap_or(&0u16);
                 card
             })
@@ -55,8 +83,9 @@ impl DeckDisplayRecord {
 
         Self {
             archetype,
-            main_deck: main_cards,
+            main_deck: grouping.group(main_cards),
             sideboard: sideboard_cards,
+            land_count,
         }
     }
 }
@@ -86,33 +115,56 @@ impl DeckDifference {
         quantities(&missing)
     }
 
-    fn aggregate(collection: &HashMap<i32, u16>, cards_database: &CardsDatabase) -> Vec<Card> {
+    fn aggregate(collection: &HashMap<i32, u16>, cards: &Cards) -> Vec<Card> {
         collection
             .iter()
             .map(|(card_id, quantity)| -> Card {
-                let mut card = cards_database
-                    .get(&card_id)
-                    .map_or_else(|| Card::new(card_id.to_string()), std::convert::Into::into);
+                let mut card = cards.card(*card_id);
                 card.quantity = *quantity;
                 card
             })
             .sorted()
             .collect()
     }
-    pub fn difference(deck1: &Deck, deck2: &Deck, cards_database: &CardsDatabase) -> Self {
+    pub fn difference(deck1: &Deck, deck2: &Deck, cards: &Cards) -> Self {
         let deck1_quantities = deck1.quantities();
         let deck2_quantities = deck2.quantities();
 
         let added = Self::missing_cards(&deck2_quantities, &deck1_quantities);
         let removed = Self::missing_cards(&deck1_quantities, &deck2_quantities);
 
-        let added = Self::aggregate(&added, cards_database);
-        let removed = Self::aggregate(&removed, cards_database);
+        let added = Self::aggregate(&added, cards);
+        let removed = Self::aggregate(&removed, cards);
 
         Self::new(added, removed)
     }
 }
 
+/// FNV-1a, unlike `DefaultHasher` it is guaranteed to stay the same across releases.
+pub(crate) fn stable_hash(bytes: impl IntoIterator<Item = u8>) -> String {
+    let hash = bytes
+        .into_iter()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+    format!("{hash:016x}")
+}
+
+/// Stable identifier for a main deck, shared by every game played with the same 60.
+pub(crate) fn deck_id(deck: &Deck) -> String {
+    stable_hash(
+        deck.quantities()
+            .into_iter()
+            .sorted()
+            .flat_map(|(card_id, quantity)| {
+                card_id
+                    .to_le_bytes()
+                    .into_iter()
+                    .chain(quantity.to_le_bytes())
+            }),
+    )
+}
+
 fn quantities(deck: &[i32]) -> HashMap<i32, u16> {
     let unique: Vec<_> = deck.iter().unique().copied().collect();
     let deck_quantities: HashMap<i32, u16> = unique
@@ -0,0 +1,89 @@
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::card::Card;
+use crate::cards::Cards;
+use crate::game_state::GameStateTracker;
+
+fn to_card(cards: &Cards, card_id: i32) -> Card {
+    cards.card(card_id)
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct LibraryCard {
+    pub card: Card,
+    pub remaining: u16,
+    pub draw_odds: f64,
+}
+
+/// What the overlay window shows for the game in progress.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct DeckTracker {
+    pub match_id: String,
+    pub game_number: i32,
+    pub library_size: usize,
+    pub library: Vec<LibraryCard>,
+    /// Not part of `library_size` or the draw odds.
+    pub sideboard: Vec<Card>,
+    pub opponent_cards: Vec<Card>,
+}
+
+impl DeckTracker {
+    pub fn from_game_state(game_state: &GameStateTracker, cards: &Cards) -> Self {
+        let library_size = game_state.library_size();
+        let seen = game_state.controller_cards_seen();
+
+        let library = game_state
+            .deck()
+            .iter()
+            .counts()
+            .into_iter()
+            .map(|(card_id, quantity)| {
+                let card = to_card(cards, *card_id);
+                let quantity = u16::try_from(quantity).unwrap_or(u16::MAX);
+                let remaining = quantity.saturating_sub(*seen.get(card_id).unwrap_or(&0));
+                #[allow(clippy::cast_precision_loss)]
+                let draw_odds = if library_size == 0 {
+                    0.0
+                } else {
+                    f64::from(remaining) / library_size as f64
+                };
+                LibraryCard {
+                    card,
+                    remaining,
+                    draw_odds,
+                }
+            })
+            .sorted_by(|a, b| a.card.cmp(&b.card))
+            .collect();
+
+        let sideboard = game_state
+            .sideboard()
+            .iter()
+            .counts()
+            .into_iter()
+            .map(|(card_id, quantity)| {
+                let mut card = to_card(cards, *card_id);
+                card.quantity = u16::try_from(quantity).unwrap_or(u16::MAX);
+                card
+            })
+            .sorted()
+            .collect();
+
+        let opponent_cards = game_state
+            .opponent_cards()
+            .iter()
+            .map(|card_id| to_card(cards, *card_id))
+            .sorted()
+            .collect();
+
+        Self {
+            match_id: game_state.match_id().to_string(),
+            game_number: game_state.game_number(),
+            library_size,
+            library,
+            sideboard,
+            opponent_cards,
+        }
+    }
+}
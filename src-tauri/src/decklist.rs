@@ -0,0 +1,371 @@
+use std::collections::HashMap;
+
+use ap_core::cards::CardDbEntry;
+use ap_core::models::deck::Deck;
+use serde::{Deserialize, Serialize};
+
+use crate::cards::Cards;
+use crate::commands::error::CommandError;
+use crate::database::ReadDb;
+use crate::deck::deck_id;
+
+/// Where a deck to inspect comes from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) enum DeckSource {
+    /// The most recent game played with the main deck identified by `deck::deck_id`.
+    DeckId(String),
+    /// A deck we played, the first game's list when `game_number` is not given.
+    Match {
+        match_id: String,
+        game_number: Option<i32>,
+    },
+    /// A pasted Arena export or MTGO list.
+    Text(String),
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ImportedDeck {
+    pub deck: Deck,
+    /// Lines naming cards we could not find in the cards database.
+    pub unresolved: Vec<String>,
+}
+
+impl DeckSource {
+    pub fn resolve(&self, db: &mut ReadDb<'_>) -> anyhow::Result<ImportedDeck> {
+        match self {
+            Self::DeckId(target_deck_id) => {
+                let mut matches = db.get_matches()?;
+                matches.sort_by_key(|mtga_match| std::cmp::Reverse(mtga_match.created_at));
+                for mtga_match in matches {
+                    let decklists = db.get_decklists(&mtga_match.id)?;
+                    if let Some(deck) = decklists
+                        .into_iter()
+                        .rev()
+                        .find(|deck| deck_id(deck) == *target_deck_id)
+                    {
+                        return Ok(ImportedDeck {
+                            deck,
+                            unresolved: Vec::new(),
+                        });
+                    }
+                }
+                Err(
+                    CommandError::not_found(format!("game played with deck {target_deck_id}"))
+                        .into(),
+                )
+            }
+            Self::Match {
+                match_id,
+                game_number,
+            } => {
+                let decklists = db.get_decklists(match_id)?;
+                let deck = decklists
+                    .into_iter()
+                    .find(|deck| game_number.map_or(true, |n| deck.game_number == n))
+                    .ok_or_else(|| {
+                        CommandError::not_found(format!("decklist for match {match_id}"))
+                    })?;
+                Ok(ImportedDeck {
+                    deck,
+                    unresolved: Vec::new(),
+                })
+            }
+            Self::Text(text) => Ok(parse_decklist(text, &db.cards)),
+        }
+    }
+}
+
+pub(crate) fn entry_name(entry: &CardDbEntry) -> &str {
+    entry
+        .card_faces
+        .as_ref()
+        .and_then(|faces| faces.first())
+        .map_or(&entry.name, |face| &face.name)
+}
+
+/// Names as typed in decklists: lowercased, with MTGO's `Fire/Ice` spelled `fire // ice`.
+fn normalize_name(name: &str) -> String {
+    let name = name.trim().to_lowercase();
+    if name.contains(" // ") {
+        name
+    } else {
+        name.replace('/', " // ")
+    }
+}
+
+/// Card name to every Arena id printed with it. Multi-faced cards are found by their full
+/// `A // B` name as well as their front face.
+fn name_index(cards: &Cards) -> HashMap<String, Vec<&CardDbEntry>> {
+    let mut index: HashMap<String, Vec<&CardDbEntry>> = HashMap::default();
+    for entry in cards.db.db.values() {
+        index
+            .entry(normalize_name(entry_name(entry)))
+            .or_default()
+            .push(entry);
+        if entry.name != entry_name(entry) {
+            index
+                .entry(normalize_name(&entry.name))
+                .or_default()
+                .push(entry);
+        }
+    }
+    for entries in index.values_mut() {
+        entries.sort_by_key(|entry| entry.id);
+    }
+    index
+}
+
+struct DecklistLine<'a> {
+    quantity: u16,
+    name: &'a str,
+    set: Option<&'a str>,
+    collector_number: Option<&'a str>,
+}
+
+/// `4 Lightning Strike (M19) 152` from Arena or `4 Lightning Strike` from MTGO.
+fn parse_line(line: &str) -> Option<DecklistLine<'_>> {
+    let (quantity, rest) = line.split_once(' ')?;
+    let quantity = quantity.trim_end_matches('x').parse().ok()?;
+    let rest = rest.trim();
+    let Some((name, printing)) = rest.split_once(" (") else {
+        return Some(DecklistLine {
+            quantity,
+            name: rest,
+            set: None,
+            collector_number: None,
+        });
+    };
+    let (set, collector_number) = printing.split_once(')')?;
+    Some(DecklistLine {
+        quantity,
+        name: name.trim(),
+        set: Some(set.trim()),
+        collector_number: Some(collector_number.trim()).filter(|number| !number.is_empty()),
+    })
+}
+
+/// Picks the exact printing when the line names one, the oldest Arena id otherwise.
+fn find_printing<'a>(
+    cards: &Cards,
+    printings: &[&'a CardDbEntry],
+    set: Option<&str>,
+    collector_number: Option<&str>,
+) -> Option<&'a CardDbEntry> {
+    let in_set = |entry: &&&CardDbEntry| set.is_some_and(|set| entry.set.eq_ignore_ascii_case(set));
+    printings
+        .iter()
+        .filter(in_set)
+        .find(|entry| {
+            collector_number.is_some_and(|number| {
+                cards
+                    .metadata(entry.id)
+                    .is_some_and(|metadata| metadata.collector_number == number)
+            })
+        })
+        .or_else(|| printings.iter().find(in_set))
+        .or_else(|| printings.first())
+        .copied()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+    About,
+    Main,
+    Sideboard,
+}
+
+/// Arena export text or an MTGO `.txt` list. Arena labels its `Deck`, `Sideboard`, `Companion`
+/// and `Commander` sections while MTGO separates the sideboard with a blank line or a
+/// `Sideboard` heading. The companion is kept in the sideboard, as the client does.
+pub(crate) fn parse_decklist(text: &str, cards: &Cards) -> ImportedDeck {
+    let index = name_index(cards);
+    let mut name = "Imported".to_string();
+    let mut mainboard = Vec::new();
+    let mut sideboard = Vec::new();
+    let mut unresolved = Vec::new();
+    let mut section = Section::Main;
+    let mut labelled = false;
+
+    for line in text.lines().map(str::trim) {
+        match line.trim_end_matches(':').to_lowercase().as_str() {
+            "" => {
+                if !labelled && section == Section::Main && !mainboard.is_empty() {
+                    section = Section::Sideboard;
+                }
+                continue;
+            }
+            "about" => {
+                section = Section::About;
+                labelled = true;
+                continue;
+            }
+            "deck" | "commander" => {
+                section = Section::Main;
+                labelled = true;
+                continue;
+            }
+            "sideboard" | "companion" => {
+                section = Section::Sideboard;
+                labelled = true;
+                continue;
+            }
+            _ => {}
+        }
+        if section == Section::About {
+            if let Some(deck_name) = line.strip_prefix("Name ") {
+                name = deck_name.trim().to_string();
+            }
+            continue;
+        }
+
+        let printing = parse_line(line).and_then(|parsed| {
+            let printings = index.get(&normalize_name(parsed.name))?;
+            let printing = find_printing(cards, printings, parsed.set, parsed.collector_number)?;
+            Some((printing.id, parsed.quantity))
+        });
+        let Some((card_id, quantity)) = printing else {
+            unresolved.push(line.to_string());
+            continue;
+        };
+        let cards = std::iter::repeat(card_id).take(quantity.into());
+        if section == Section::Sideboard {
+            sideboard.extend(cards);
+        } else {
+            mainboard.extend(cards);
+        }
+    }
+
+    ImportedDeck {
+        deck: Deck::new(name, 1, mainboard, sideboard),
+        unresolved,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ap_core::cards::CardsDatabase;
+    use serde_json::json;
+
+    use super::*;
+    use crate::card_metadata::CardMetadata;
+
+    fn card(id: i32, name: &str, set: &str) -> serde_json::Value {
+        json!({
+            "id": id,
+            "set": set,
+            "name": name,
+            "lang": "en",
+            "image_uri": null,
+            "mana_cost": "",
+            "cmc": 0.0,
+            "type_line": "",
+            "layout": "normal",
+            "colors": null,
+            "color_identity": [],
+            "card_faces": null,
+        })
+    }
+
+    fn cards() -> Cards {
+        let mut fire_ice = card(3, "Fire // Ice", "mh2");
+        fire_ice["layout"] = json!("split");
+        fire_ice["card_faces"] = json!([
+            { "name": "Fire", "type_line": "Instant", "mana_cost": "{1}{R}" },
+            { "name": "Ice", "type_line": "Instant", "mana_cost": "{1}{U}" },
+        ]);
+        let cards = [
+            card(1, "Lightning Strike", "m19"),
+            card(2, "Lightning Strike", "xln"),
+            fire_ice,
+            card(4, "Lim-Dûl's Vault", "all"),
+            card(5, "Lightning Strike", "m19"),
+        ];
+        let db = CardsDatabase {
+            db: cards
+                .into_iter()
+                .map(|card| {
+                    let entry: CardDbEntry =
+                        serde_json::from_value(card).expect("Could not read the test card");
+                    (entry.id.to_string(), entry)
+                })
+                .collect(),
+        };
+        let metadata = [(1, "152"), (5, "301")]
+            .into_iter()
+            .map(|(card_id, collector_number)| {
+                let metadata = CardMetadata {
+                    collector_number: collector_number.to_string(),
+                    ..CardMetadata::default()
+                };
+                (card_id, metadata)
+            })
+            .collect();
+        Cards::new(db, metadata)
+    }
+
+    fn counts(quantities: HashMap<i32, u16>) -> Vec<(i32, u16)> {
+        let mut counts: Vec<_> = quantities.into_iter().collect();
+        counts.sort_unstable();
+        counts
+    }
+
+    #[test]
+    fn reads_arena_sections() {
+        let imported = parse_decklist(
+            indoc::indoc! {"
+                About
+                Name Izzet Burn
+
+                Companion
+                1 Lim-Dûl's Vault (ALL) 130
+
+                Deck
+                4 Lightning Strike (XLN) 149
+
+                Sideboard
+                2 Fire // Ice (MH2) 290
+            "},
+            &cards(),
+        );
+        assert_eq!(imported.deck.name, "Izzet Burn");
+        assert_eq!(counts(imported.deck.quantities()), [(2, 4)]);
+        assert_eq!(
+            counts(imported.deck.sideboard_quantities()),
+            [(3, 2), (4, 1)]
+        );
+        assert!(imported.unresolved.is_empty());
+    }
+
+    #[test]
+    fn reads_mtgo_sideboard_after_a_blank_line() {
+        let imported = parse_decklist("4 Lightning Strike\n2x Fire/Ice\n\n1 Fire\n", &cards());
+        assert_eq!(counts(imported.deck.quantities()), [(1, 4), (3, 2)]);
+        assert_eq!(counts(imported.deck.sideboard_quantities()), [(3, 1)]);
+    }
+
+    #[test]
+    fn keeps_blank_lines_within_labelled_sections() {
+        let imported = parse_decklist("Deck\n4 Lightning Strike\n\n1 Fire // Ice\n", &cards());
+        assert_eq!(counts(imported.deck.quantities()), [(1, 4), (3, 1)]);
+        assert!(imported.deck.sideboard_quantities().is_empty());
+    }
+
+    #[test]
+    fn picks_the_printing_by_collector_number() {
+        let imported = parse_decklist("4 Lightning Strike (M19) 301\n", &cards());
+        assert_eq!(counts(imported.deck.quantities()), [(5, 4)]);
+    }
+
+    #[test]
+    fn falls_back_to_the_oldest_printing() {
+        let imported = parse_decklist("4 Lightning Strike (DOM) 1\n", &cards());
+        assert_eq!(counts(imported.deck.quantities()), [(1, 4)]);
+    }
+
+    #[test]
+    fn reports_unresolved_lines() {
+        let imported = parse_decklist("4 Lightning Strike\n3 Shock\nnonsense\n", &cards());
+        assert_eq!(counts(imported.deck.quantities()), [(1, 4)]);
+        assert_eq!(imported.unresolved, ["3 Shock", "nonsense"]);
+    }
+}
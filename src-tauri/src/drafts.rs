@@ -0,0 +1,201 @@
+use chrono::{DateTime, Utc};
+use indoc::indoc;
+use itertools::Itertools;
+use rusqlite::Connection;
+use serde_json::Value;
+
+use crate::client_log::{embedded_json, ClientLogEntry};
+use crate::deck::stable_hash;
+
+/// A pack shown to the player or the pick they made from it. Pack and pick numbers are 1-based.
+#[derive(Debug, Clone)]
+pub(crate) enum DraftEvent {
+    Pack {
+        draft_id: String,
+        event_name: String,
+        pack_number: i32,
+        pick_number: i32,
+        card_ids: Vec<i32>,
+    },
+    Pick {
+        draft_id: String,
+        pack_number: i32,
+        pick_number: i32,
+        card_id: i32,
+    },
+}
+
+impl DraftEvent {
+    /// A draft is dated by when its first pack was logged, so reading the log again keeps it.
+    pub fn write(&self, conn: &Connection, logged_at: DateTime<Utc>) -> rusqlite::Result<()> {
+        match self {
+            Self::Pack {
+                draft_id,
+                event_name,
+                pack_number,
+                pick_number,
+                card_ids,
+            } => {
+                conn.execute(
+                    "INSERT OR IGNORE INTO drafts (id, event_name, created_at) VALUES (?1, ?2, ?3)",
+                    (draft_id, event_name, logged_at),
+                )?;
+                conn.execute(
+                    indoc! {r"
+                        INSERT INTO draft_picks (draft_id, pack_number, pick_number, pack_cards)
+                        VALUES (?1, ?2, ?3, ?4)
+                        ON CONFLICT (draft_id, pack_number, pick_number)
+                        DO UPDATE SET pack_cards = excluded.pack_cards
+                    "},
+                    (
+                        draft_id,
+                        pack_number,
+                        pick_number,
+                        card_ids.iter().join(","),
+                    ),
+                )?;
+            }
+            Self::Pick {
+                draft_id,
+                pack_number,
+                pick_number,
+                card_id,
+            } => {
+                conn.execute(
+                    indoc! {r"
+                        INSERT INTO draft_picks (draft_id, pack_number, pick_number, pack_cards, picked_card_id)
+                        VALUES (?1, ?2, ?3, '', ?4)
+                        ON CONFLICT (draft_id, pack_number, pick_number)
+                        DO UPDATE SET picked_card_id = excluded.picked_card_id
+                    "},
+                    (draft_id, pack_number, pick_number, card_id),
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Follows human (`Draft.Notify` / `Event_PlayerDraftMakePick`) and bot
+/// (`BotDraft_DraftStatus` / `BotDraft_DraftPick`) drafts.
+#[derive(Debug, Default)]
+pub(crate) struct DraftTracker {
+    event_name: String,
+    draft_id: String,
+    /// Pack and pick number of the last pack shown, to tell a draft going on from one resumed.
+    position: (i32, i32),
+}
+
+fn card_ids(value: Option<&Value>) -> Vec<i32> {
+    match value {
+        // human drafts send packs as a comma separated string, bot drafts as a list of strings
+        Some(Value::String(card_ids)) => card_ids
+            .split(',')
+            .filter_map(|card_id| card_id.trim().parse().ok())
+            .collect(),
+        Some(Value::Array(card_ids)) => card_ids
+            .iter()
+            .filter_map(|card_id| match card_id {
+                Value::String(card_id) => card_id.parse().ok(),
+                _ => card_id.as_i64().and_then(|id| i32::try_from(id).ok()),
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn number(value: &Value, key: &str) -> Option<i32> {
+    value
+        .get(key)
+        .and_then(Value::as_i64)
+        .and_then(|n| i32::try_from(n).ok())
+}
+
+impl DraftTracker {
+    pub fn ingest_entry(&mut self, entry: &ClientLogEntry) -> Option<DraftEvent> {
+        match entry {
+            ClientLogEntry::Request { method, payload } => {
+                let request = embedded_json(payload, "request")?;
+                match method.as_str() {
+                    "Event_Join" => {
+                        self.event_name = request.get("EventName")?.as_str()?.to_string();
+                        self.draft_id.clear();
+                        None
+                    }
+                    "Event_PlayerDraftMakePick" => {
+                        let card_id = card_ids(request.get("GrpIds"))
+                            .first()
+                            .copied()
+                            .or_else(|| number(&request, "GrpId"))?;
+                        Some(DraftEvent::Pick {
+                            draft_id: request.get("DraftId")?.as_str()?.to_string(),
+                            pack_number: number(&request, "Pack")?,
+                            pick_number: number(&request, "Pick")?,
+                            card_id,
+                        })
+                    }
+                    "BotDraft_DraftPick" if !self.draft_id.is_empty() => {
+                        let pick_info = request.get("PickInfo")?;
+                        Some(DraftEvent::Pick {
+                            draft_id: self.draft_id.clone(),
+                            pack_number: number(pick_info, "PackNumber")? + 1,
+                            pick_number: number(pick_info, "PickNumber")? + 1,
+                            card_id: *card_ids(pick_info.get("CardIds")).first()?,
+                        })
+                    }
+                    _ => None,
+                }
+            }
+            ClientLogEntry::Response { method, payload } => match method.as_str() {
+                "Draft.Notify" => {
+                    self.draft_id = payload.get("draftId")?.as_str()?.to_string();
+                    self.position = (number(payload, "SelfPack")?, number(payload, "SelfPick")?);
+                    Some(DraftEvent::Pack {
+                        draft_id: self.draft_id.clone(),
+                        event_name: self.event_name.clone(),
+                        pack_number: self.position.0,
+                        pick_number: self.position.1,
+                        card_ids: card_ids(payload.get("PackCards")),
+                    })
+                }
+                "BotDraft_DraftStatus" | "BotDraft_DraftPick" => {
+                    self.ingest_bot_draft_status(&embedded_json(payload, "Payload")?)
+                }
+                _ => None,
+            },
+            ClientLogEntry::Message(_) => None,
+        }
+    }
+
+    #[allow(clippy::similar_names)]
+    fn ingest_bot_draft_status(&mut self, status: &Value) -> Option<DraftEvent> {
+        let event_name = status.get("EventName")?.as_str()?.to_string();
+        let pack_number = number(status, "PackNumber")? + 1;
+        let pick_number = number(status, "PickNumber")? + 1;
+        let card_ids = card_ids(status.get("DraftPack"));
+        if card_ids.is_empty() {
+            return None;
+        }
+        // Bot drafts have no id of their own, key them on the first pack we saw of them. A pack
+        // of another event or one further back than the last is a different draft, resumed.
+        let resumed = self.draft_id.is_empty()
+            || event_name != self.event_name
+            || (pack_number, pick_number) < self.position;
+        if (pack_number == 1 && pick_number == 1) || resumed {
+            self.draft_id = format!(
+                "{}-{}",
+                event_name,
+                stable_hash(card_ids.iter().flat_map(|card_id| card_id.to_le_bytes()))
+            );
+        }
+        self.event_name.clone_from(&event_name);
+        self.position = (pack_number, pick_number);
+        Some(DraftEvent::Pack {
+            draft_id: self.draft_id.clone(),
+            event_name,
+            pack_number,
+            pick_number,
+            card_ids,
+        })
+    }
+}
@@ -0,0 +1,201 @@
+use chrono::{DateTime, Utc};
+use indoc::indoc;
+use itertools::Itertools;
+use rusqlite::Connection;
+use serde_json::Value;
+
+use crate::client_log::ClientLogEntry;
+
+/// A single entry into an event, as reported by the client's `Course` payloads.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Course {
+    pub id: String,
+    pub event_name: String,
+    pub current_module: String,
+    pub wins: i32,
+    pub losses: i32,
+    pub card_pool: Vec<i32>,
+    pub main_deck: Vec<i32>,
+    pub sideboard: Vec<i32>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum EventRunEvent {
+    /// A course from the client's listing of every event the player is in.
+    Course(Course),
+    /// A course the player just joined, queued into or submitted a deck to.
+    Entered(Course),
+    Prize {
+        course: Course,
+        prizes: Value,
+    },
+}
+
+fn number(value: &Value, key: &str) -> i32 {
+    value
+        .get(key)
+        .and_then(Value::as_i64)
+        .and_then(|n| i32::try_from(n).ok())
+        .unwrap_or_default()
+}
+
+/// `[{"cardId": 1, "quantity": 2}]` into `[1, 1]`.
+fn deck_cards(value: Option<&Value>) -> Vec<i32> {
+    value
+        .and_then(Value::as_array)
+        .map(|cards| {
+            cards
+                .iter()
+                .flat_map(|card| {
+                    let card_id = number(card, "cardId");
+                    let quantity = usize::try_from(number(card, "quantity")).unwrap_or_default();
+                    std::iter::repeat(card_id).take(quantity)
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+impl Course {
+    fn from_payload(course: &Value) -> Option<Self> {
+        let deck = course.get("CourseDeck");
+        Some(Self {
+            id: course.get("CourseId")?.as_str()?.to_string(),
+            event_name: course.get("InternalEventName")?.as_str()?.to_string(),
+            current_module: course
+                .get("CurrentModule")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            wins: number(course, "CurrentWins"),
+            losses: number(course, "CurrentLosses"),
+            card_pool: course
+                .get("CardPool")
+                .and_then(Value::as_array)
+                .map(|pool| {
+                    pool.iter()
+                        .filter_map(Value::as_i64)
+                        .filter_map(|card_id| i32::try_from(card_id).ok())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            main_deck: deck_cards(deck.and_then(|deck| deck.get("MainDeck"))),
+            sideboard: deck_cards(deck.and_then(|deck| deck.get("Sideboard"))),
+        })
+    }
+
+    /// `entered` marks the run as the one the player is playing now, which a listing doesn't.
+    fn write(
+        &self,
+        conn: &Connection,
+        logged_at: DateTime<Utc>,
+        entered: bool,
+    ) -> rusqlite::Result<()> {
+        conn.execute(
+            indoc! {r"
+                INSERT INTO event_runs (
+                    course_id, event_name, current_module, wins, losses, card_pool, main_deck,
+                    sideboard, created_at, updated_at, entered_at
+                )
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?9, ?10)
+                ON CONFLICT (course_id) DO UPDATE SET
+                    current_module = excluded.current_module,
+                    wins = excluded.wins,
+                    losses = excluded.losses,
+                    card_pool = excluded.card_pool,
+                    main_deck = excluded.main_deck,
+                    sideboard = excluded.sideboard,
+                    updated_at = excluded.updated_at,
+                    entered_at = COALESCE(excluded.entered_at, entered_at)
+            "},
+            (
+                &self.id,
+                &self.event_name,
+                &self.current_module,
+                self.wins,
+                self.losses,
+                self.card_pool.iter().join(","),
+                self.main_deck.iter().join(","),
+                self.sideboard.iter().join(","),
+                logged_at,
+                entered.then_some(logged_at),
+            ),
+        )?;
+        Ok(())
+    }
+}
+
+impl EventRunEvent {
+    /// Runs are dated by when the client logged them, so reading the log again changes nothing.
+    pub fn write(&self, conn: &Connection, logged_at: DateTime<Utc>) -> rusqlite::Result<()> {
+        match self {
+            Self::Course(course) => course.write(conn, logged_at, false),
+            Self::Entered(course) => course.write(conn, logged_at, true),
+            Self::Prize { course, prizes } => {
+                course.write(conn, logged_at, false)?;
+                conn.execute(
+                    "UPDATE event_runs SET prizes = ?2 WHERE course_id = ?1",
+                    (&course.id, prizes.to_string()),
+                )?;
+                Ok(())
+            }
+        }
+    }
+
+    pub fn from_entry(entry: &ClientLogEntry) -> Vec<Self> {
+        let ClientLogEntry::Response { method, payload } = entry else {
+            return Vec::new();
+        };
+        match method.as_str() {
+            "Event_GetCourses" | "Event_GetCoursesV2" => payload
+                .get("Courses")
+                .and_then(Value::as_array)
+                .map(|courses| {
+                    courses
+                        .iter()
+                        .filter_map(Course::from_payload)
+                        .map(Self::Course)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            "Event_Join" | "Event_EnterPairing" | "Event_SetDeck" | "Event_SetDeckV2" => payload
+                .get("Course")
+                .and_then(Course::from_payload)
+                .map(Self::Entered)
+                .into_iter()
+                .collect(),
+            "Event_ClaimPrize" => payload
+                .get("Course")
+                .and_then(Course::from_payload)
+                .map(|course| Self::Prize {
+                    course,
+                    prizes: payload
+                        .pointer("/InventoryInfo/Changes")
+                        .cloned()
+                        .unwrap_or_default(),
+                })
+                .into_iter()
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Ties a match to the run of its event the player last joined, queued into or submitted a deck
+/// to. Match messages only carry the event name, and listings touch every run of an event alike.
+pub(crate) fn link_match(
+    conn: &Connection,
+    event_name: &str,
+    match_id: &str,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        indoc! {r"
+            INSERT OR IGNORE INTO event_run_matches (course_id, match_id)
+            SELECT course_id, ?2 FROM event_runs
+            WHERE event_name = ?1
+            ORDER BY entered_at DESC NULLS LAST, updated_at DESC LIMIT 1
+        "},
+        (event_name, match_id),
+    )?;
+    Ok(())
+}
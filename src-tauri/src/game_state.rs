@@ -0,0 +1,486 @@
+use std::collections::{HashMap, HashSet};
+
+use rusqlite::Connection;
+use serde_json::Value;
+
+/// Zones a card drawn or milled from the library can end up in.
+const LEFT_LIBRARY_ZONES: &[&str] = &[
+    "ZoneType_Hand",
+    "ZoneType_Battlefield",
+    "ZoneType_Graveyard",
+    "ZoneType_Exile",
+    "ZoneType_Stack",
+];
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Zone {
+    pub kind: String,
+    pub owner_seat_id: i64,
+    pub instance_ids: Vec<i64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct GameObject {
+    pub grp_id: i32,
+    pub owner_seat_id: i64,
+    pub object_type: String,
+    pub visibility: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Player {
+    pub seat_id: i64,
+    pub name: String,
+    pub event_id: String,
+    pub life_total: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TurnInfo {
+    pub turn_number: i64,
+    pub active_seat_id: i64,
+    pub phase: String,
+    pub step: String,
+}
+
+/// A finished game, the cards the controller drew over its course and the ones the opponent
+/// revealed.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct GameDraws {
+    pub match_id: String,
+    pub game_number: i32,
+    pub draws: HashMap<i32, u16>,
+    pub opponent_cards: HashSet<i32>,
+}
+
+impl GameDraws {
+    pub fn write(&self, conn: &Connection) -> rusqlite::Result<()> {
+        conn.execute(
+            "DELETE FROM game_draws WHERE match_id = ?1 AND game_number = ?2",
+            (&self.match_id, self.game_number),
+        )?;
+        let mut statement = conn.prepare(
+            "INSERT INTO game_draws (match_id, game_number, card_id, quantity) VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for (card_id, quantity) in &self.draws {
+            statement.execute((&self.match_id, self.game_number, card_id, quantity))?;
+        }
+
+        conn.execute(
+            "DELETE FROM opponent_cards WHERE match_id = ?1 AND game_number = ?2",
+            (&self.match_id, self.game_number),
+        )?;
+        let mut statement = conn.prepare(
+            "INSERT INTO opponent_cards (match_id, game_number, card_id) VALUES (?1, ?2, ?3)",
+        )?;
+        for card_id in &self.opponent_cards {
+            statement.execute((&self.match_id, self.game_number, card_id))?;
+        }
+        Ok(())
+    }
+}
+
+/// Follows GRE game state messages, keeping the zones and objects of the game in progress and
+/// recording every card the controller draws.
+#[derive(Debug, Default)]
+pub(crate) struct GameStateTracker {
+    match_id: String,
+    game_number: i32,
+    controller_seat_id: i64,
+    players: Vec<Player>,
+    match_win_condition: String,
+    turn_info: TurnInfo,
+    match_complete: bool,
+    deck: Vec<i32>,
+    sideboard: Vec<i32>,
+    zones: HashMap<i64, Zone>,
+    objects: HashMap<i64, GameObject>,
+    opponent_cards: HashSet<i32>,
+    draws: HashMap<i32, u16>,
+    game_over: bool,
+    changed: bool,
+}
+
+impl GameStateTracker {
+    /// Feeds a bare JSON log message, returning the draws of a game once it is over.
+    pub fn ingest_message(&mut self, message: &Value) -> Option<GameDraws> {
+        if let Some(game_room_info) =
+            message.pointer("/matchGameRoomStateChangedEvent/gameRoomInfo")
+        {
+            self.ingest_game_room_info(game_room_info);
+            return None;
+        }
+
+        let gre_messages = message
+            .get("greToClientEvent")?
+            .get("greToClientMessages")?
+            .as_array()?;
+
+        let mut finished = None;
+        for gre_message in gre_messages {
+            if let Some(seat_id) = gre_message
+                .get("systemSeatIds")
+                .and_then(|ids| ids.get(0))
+                .and_then(Value::as_i64)
+            {
+                self.controller_seat_id = seat_id;
+            }
+            if let Some(deck_message) = gre_message
+                .pointer("/connectResp/deckMessage")
+                .or_else(|| gre_message.pointer("/submitDeckReq/deck"))
+                .filter(|deck_message| deck_message.get("deckCards").is_some())
+            {
+                self.deck = card_ids(deck_message, "deckCards");
+                self.sideboard = card_ids(deck_message, "sideboardCards");
+                self.changed = true;
+            }
+            if let Some(game_state) = gre_message.get("gameStateMessage") {
+                if let Some(game_draws) = self.ingest_game_state(game_state) {
+                    finished = Some(game_draws);
+                }
+            }
+        }
+        finished
+    }
+
+    fn ingest_game_state(&mut self, game_state: &Value) -> Option<GameDraws> {
+        if let Some(game_info) = game_state.get("gameInfo") {
+            let match_id = game_info.get("matchID").and_then(Value::as_str);
+            let game_number = game_info
+                .get("gameNumber")
+                .and_then(Value::as_i64)
+                .and_then(|n| i32::try_from(n).ok());
+            if let (Some(match_id), Some(game_number)) = (match_id, game_number) {
+                if match_id != self.match_id || game_number != self.game_number {
+                    self.start_game(match_id, game_number);
+                }
+            }
+            if let Some(match_win_condition) =
+                game_info.get("matchWinCondition").and_then(Value::as_str)
+            {
+                self.match_win_condition = match_win_condition.to_string();
+            }
+        }
+
+        self.ingest_turn_and_players(game_state);
+
+        for zone in json_array(game_state, "zones") {
+            if let Some(zone_id) = zone.get("zoneId").and_then(Value::as_i64) {
+                self.zones.insert(
+                    zone_id,
+                    Zone {
+                        kind: json_str(zone, "type"),
+                        owner_seat_id: zone.get("ownerSeatId").and_then(Value::as_i64).unwrap_or(0),
+                        instance_ids: json_array(zone, "objectInstanceIds")
+                            .iter()
+                            .filter_map(Value::as_i64)
+                            .collect(),
+                    },
+                );
+                self.changed = true;
+            }
+        }
+
+        for game_object in json_array(game_state, "gameObjects") {
+            let instance_id = game_object.get("instanceId").and_then(Value::as_i64);
+            let grp_id = game_object
+                .get("grpId")
+                .and_then(Value::as_i64)
+                .and_then(|id| i32::try_from(id).ok());
+            if let (Some(instance_id), Some(grp_id)) = (instance_id, grp_id) {
+                let game_object = GameObject {
+                    grp_id,
+                    owner_seat_id: game_object
+                        .get("ownerSeatId")
+                        .and_then(Value::as_i64)
+                        .unwrap_or(0),
+                    object_type: json_str(game_object, "type"),
+                    visibility: json_str(game_object, "visibility"),
+                };
+                if game_object.owner_seat_id != self.controller_seat_id
+                    && game_object.object_type == "GameObjectType_Card"
+                    && game_object.visibility == "Visibility_Public"
+                {
+                    self.opponent_cards.insert(grp_id);
+                }
+                self.objects.insert(instance_id, game_object);
+                self.changed = true;
+            }
+        }
+
+        for instance_id in json_array(game_state, "diffDeletedInstanceIds") {
+            if let Some(instance_id) = instance_id.as_i64() {
+                self.objects.remove(&instance_id);
+            }
+        }
+
+        for annotation in json_array(game_state, "annotations") {
+            self.ingest_annotation(annotation);
+        }
+
+        let stage = game_state
+            .get("gameInfo")
+            .and_then(|game_info| game_info.get("stage"))
+            .and_then(Value::as_str);
+        if stage == Some("GameStage_GameOver") && !self.game_over && !self.match_id.is_empty() {
+            self.game_over = true;
+            return Some(GameDraws {
+                match_id: self.match_id.clone(),
+                game_number: self.game_number,
+                draws: self.draws.clone(),
+                opponent_cards: self.opponent_cards.clone(),
+            });
+        }
+        None
+    }
+
+    fn ingest_turn_and_players(&mut self, game_state: &Value) {
+        if let Some(turn_info) = game_state.get("turnInfo") {
+            self.turn_info = TurnInfo {
+                turn_number: turn_info
+                    .get("turnNumber")
+                    .and_then(Value::as_i64)
+                    .unwrap_or(self.turn_info.turn_number),
+                active_seat_id: turn_info
+                    .get("activePlayer")
+                    .and_then(Value::as_i64)
+                    .unwrap_or(self.turn_info.active_seat_id),
+                phase: json_str(turn_info, "phase"),
+                step: json_str(turn_info, "step"),
+            };
+            self.changed = true;
+        }
+
+        for player in json_array(game_state, "players") {
+            let seat_id = player.get("systemSeatNumber").and_then(Value::as_i64);
+            let life_total = player.get("lifeTotal").and_then(Value::as_i64);
+            if let (Some(seat_id), Some(life_total)) = (seat_id, life_total) {
+                self.player_mut(seat_id).life_total = Some(life_total);
+                self.changed = true;
+            }
+        }
+    }
+
+    fn ingest_game_room_info(&mut self, game_room_info: &Value) {
+        let config = game_room_info.get("gameRoomConfig");
+        if let Some(match_id) = config
+            .and_then(|config| config.get("matchId"))
+            .and_then(Value::as_str)
+        {
+            if match_id != self.match_id {
+                self.start_game(match_id, 1);
+                self.players.clear();
+                self.match_win_condition.clear();
+                self.turn_info = TurnInfo::default();
+                self.match_complete = false;
+            }
+        }
+
+        for reserved_player in
+            config.map_or(&[][..], |config| json_array(config, "reservedPlayers"))
+        {
+            if let Some(seat_id) = reserved_player.get("systemSeatId").and_then(Value::as_i64) {
+                let player = self.player_mut(seat_id);
+                player.name = json_str(reserved_player, "playerName");
+                player.event_id = json_str(reserved_player, "eventId");
+            }
+        }
+
+        if json_str(game_room_info, "stateType") == "MatchGameRoomStateType_MatchCompleted" {
+            self.match_complete = true;
+        }
+        self.changed = true;
+    }
+
+    fn player_mut(&mut self, seat_id: i64) -> &mut Player {
+        if let Some(index) = self.players.iter().position(|p| p.seat_id == seat_id) {
+            &mut self.players[index]
+        } else {
+            self.players.push(Player {
+                seat_id,
+                ..Default::default()
+            });
+            self.players.sort_by_key(|p| p.seat_id);
+            self.player_mut(seat_id)
+        }
+    }
+
+    fn ingest_annotation(&mut self, annotation: &Value) {
+        let is_zone_transfer = json_array(annotation, "type")
+            .iter()
+            .any(|t| t.as_str() == Some("AnnotationType_ZoneTransfer"));
+        if !is_zone_transfer {
+            return;
+        }
+
+        let mut category = None;
+        let mut zone_dest = None;
+        for detail in json_array(annotation, "details") {
+            match detail.get("key").and_then(Value::as_str) {
+                Some("category") => {
+                    category = detail
+                        .get("valueString")
+                        .and_then(|v| v.get(0))
+                        .and_then(Value::as_str);
+                }
+                Some("zone_dest") => {
+                    zone_dest = detail
+                        .get("valueInt32")
+                        .and_then(|v| v.get(0))
+                        .and_then(Value::as_i64);
+                }
+                _ => {}
+            }
+        }
+
+        if category != Some("Draw") {
+            return;
+        }
+        let Some(zone) = zone_dest.and_then(|zone_id| self.zones.get(&zone_id)) else {
+            return;
+        };
+        if zone.kind != "ZoneType_Hand" || zone.owner_seat_id != self.controller_seat_id {
+            return;
+        }
+
+        for instance_id in json_array(annotation, "affectedIds") {
+            if let Some(game_object) = instance_id.as_i64().and_then(|id| self.objects.get(&id)) {
+                *self.draws.entry(game_object.grp_id).or_default() += 1;
+            }
+        }
+    }
+
+    fn start_game(&mut self, match_id: &str, game_number: i32) {
+        self.match_id = match_id.to_string();
+        self.game_number = game_number;
+        self.zones.clear();
+        self.objects.clear();
+        self.opponent_cards.clear();
+        self.draws.clear();
+        self.game_over = false;
+        self.changed = true;
+    }
+
+    pub fn match_id(&self) -> &str {
+        &self.match_id
+    }
+
+    pub fn game_number(&self) -> i32 {
+        self.game_number
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.game_over
+    }
+
+    pub fn is_match_complete(&self) -> bool {
+        self.match_complete
+    }
+
+    /// The event the match was queued from, e.g. `Ladder` or `PremierDraft_MKM_20240206`.
+    pub fn event_id(&self) -> &str {
+        self.players
+            .iter()
+            .map(|p| p.event_id.as_str())
+            .find(|event_id| !event_id.is_empty())
+            .unwrap_or_default()
+    }
+
+    pub fn match_win_condition(&self) -> &str {
+        &self.match_win_condition
+    }
+
+    pub fn controller_seat_id(&self) -> i64 {
+        self.controller_seat_id
+    }
+
+    pub fn players(&self) -> &[Player] {
+        &self.players
+    }
+
+    pub fn turn_info(&self) -> &TurnInfo {
+        &self.turn_info
+    }
+
+    pub fn zones(&self) -> impl Iterator<Item = (&i64, &Zone)> {
+        self.zones.iter()
+    }
+
+    pub fn object(&self, instance_id: i64) -> Option<&GameObject> {
+        self.objects.get(&instance_id)
+    }
+
+    /// Whether anything changed since the last call.
+    pub fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
+
+    /// The main deck of the game in progress, without the sideboard.
+    pub fn deck(&self) -> &[i32] {
+        &self.deck
+    }
+
+    pub fn sideboard(&self) -> &[i32] {
+        &self.sideboard
+    }
+
+    pub fn library_size(&self) -> usize {
+        self.zones
+            .values()
+            .filter(|zone| {
+                zone.kind == "ZoneType_Library" && zone.owner_seat_id == self.controller_seat_id
+            })
+            .map(|zone| zone.instance_ids.len())
+            .sum()
+    }
+
+    /// Copies of each of the controller's cards that left their library for good. Sideboard,
+    /// revealed and limbo copies are either not part of the deck or still counted in the library.
+    pub fn controller_cards_seen(&self) -> HashMap<i32, u16> {
+        let mut seen: HashMap<i32, u16> = HashMap::new();
+        for zone in self.zones.values() {
+            if !LEFT_LIBRARY_ZONES.contains(&zone.kind.as_str()) {
+                continue;
+            }
+            for game_object in zone
+                .instance_ids
+                .iter()
+                .filter_map(|instance_id| self.objects.get(instance_id))
+                .filter(|game_object| {
+                    game_object.owner_seat_id == self.controller_seat_id
+                        && game_object.object_type == "GameObjectType_Card"
+                })
+            {
+                *seen.entry(game_object.grp_id).or_default() += 1;
+            }
+        }
+        seen
+    }
+
+    pub fn opponent_cards(&self) -> &HashSet<i32> {
+        &self.opponent_cards
+    }
+}
+
+fn json_array<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+    value
+        .get(key)
+        .and_then(Value::as_array)
+        .map_or(&[], Vec::as_slice)
+}
+
+fn card_ids(value: &Value, key: &str) -> Vec<i32> {
+    json_array(value, key)
+        .iter()
+        .filter_map(Value::as_i64)
+        .filter_map(|card_id| i32::try_from(card_id).ok())
+        .collect()
+}
+
+fn json_str(value: &Value, key: &str) -> String {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
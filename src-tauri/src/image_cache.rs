@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::bail;
+use itertools::Itertools;
+use tauri::http::{header, Request, Response, StatusCode};
+use tauri::{Manager, UriSchemeContext, UriSchemeResponder, Wry};
+use tracing::{error, info};
+
+use crate::database::{recover, DatabaseState, ReadDb};
+use crate::deck::stable_hash;
+
+/// Scheme the frontend loads card images through, `convertFileSrc(imageUri, "cardimg")`.
+pub(crate) const URI_SCHEME: &str = "cardimg";
+
+/// Card images are only fetched from Scryfall's image CDN.
+const IMAGE_ORIGIN: &str = "https://cards.scryfall.io/";
+
+/// Roughly a few thousand Scryfall `normal` images.
+const MAX_CACHE_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Eviction goes a little below the limit so the next few downloads don't trigger it again.
+const EVICT_TO_BYTES: u64 = MAX_CACHE_BYTES / 10 * 9;
+
+/// Far above any card image, `png` ones are around 1MB.
+const MAX_IMAGE_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Scryfall asks API clients to keep 50-100ms between requests.
+const DOWNLOAD_DELAY: Duration = Duration::from_millis(100);
+
+/// Threads serving image requests, downloads are one at a time anyway so these mostly read the
+/// cache while one of them waits on Scryfall.
+const WORKERS: usize = 4;
+
+/// Requests waiting for a worker, past this the webview gets the placeholder right away.
+const MAX_QUEUED_REQUESTS: usize = 256;
+
+const PLACEHOLDER: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" width="488" height="680" viewBox="0 0 488 680"><rect width="488" height="680" rx="24" fill="#1f2937"/><text x="244" y="340" fill="#9ca3af" font-family="sans-serif" font-size="32" text-anchor="middle">Image unavailable</text></svg>"##;
+
+/// Every file in the cache directory with when it was last read and its size.
+fn cached_files(dir: &Path) -> std::io::Result<Vec<(SystemTime, u64, PathBuf)>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        files.push((metadata.modified()?, metadata.len(), entry.path()));
+    }
+    Ok(files)
+}
+
+/// Card images downloaded once and kept under `app_data_dir/images`, keyed by the hash of their
+/// remote URL. Files are touched on every read so eviction drops the least recently used.
+#[derive(Debug)]
+pub(crate) struct ImageCache {
+    dir: PathBuf,
+    /// Bytes cached, held while writing and evicting so concurrent downloads don't race on it.
+    size: Mutex<u64>,
+    /// One lock per URL being downloaded, so a second request for it waits for the first
+    /// instead of fetching it again.
+    downloads: Mutex<HashMap<String, Arc<Mutex<()>>>>,
+    /// When the last download started, held while waiting out `DOWNLOAD_DELAY`.
+    last_download: Mutex<Option<Instant>>,
+}
+
+impl ImageCache {
+    pub fn new(dir: PathBuf) -> std::io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        let size = cached_files(&dir)?.iter().map(|(_, len, _)| len).sum();
+        Ok(Self {
+            dir,
+            size: Mutex::new(size),
+            downloads: Mutex::default(),
+            last_download: Mutex::default(),
+        })
+    }
+
+    fn path_for(&self, url: &str) -> PathBuf {
+        self.dir.join(stable_hash(url.bytes()))
+    }
+
+    pub fn contains(&self, url: &str) -> bool {
+        self.path_for(url).exists()
+    }
+
+    fn read(path: &Path) -> std::io::Result<Vec<u8>> {
+        let bytes = fs::read(path)?;
+        File::options()
+            .write(true)
+            .open(path)?
+            .set_modified(SystemTime::now())?;
+        Ok(bytes)
+    }
+
+    fn download(&self, url: &str) -> anyhow::Result<Vec<u8>> {
+        if !url.starts_with(IMAGE_ORIGIN) {
+            bail!("not a Scryfall image: {url}");
+        }
+        {
+            let mut last_download = recover(self.last_download.lock(), "image downloads");
+            if let Some(wait) = last_download
+                .and_then(|last| (last + DOWNLOAD_DELAY).checked_duration_since(Instant::now()))
+            {
+                std::thread::sleep(wait);
+            }
+            *last_download = Some(Instant::now());
+        }
+        let mut bytes = Vec::new();
+        ureq::AgentBuilder::new()
+            .redirects(0)
+            .build()
+            .get(url)
+            .set("User-Agent", "arena-buddy")
+            .call()?
+            .into_reader()
+            .take(MAX_IMAGE_BYTES + 1)
+            .read_to_end(&mut bytes)?;
+        if bytes.len() as u64 > MAX_IMAGE_BYTES {
+            bail!("image over {MAX_IMAGE_BYTES} bytes: {url}");
+        }
+        Ok(bytes)
+    }
+
+    /// The cached image, downloading it first when we don't have it yet.
+    pub fn get(&self, url: &str) -> anyhow::Result<Vec<u8>> {
+        let path = self.path_for(url);
+        if let Ok(bytes) = Self::read(&path) {
+            return Ok(bytes);
+        }
+        let download = recover(self.downloads.lock(), "image downloads")
+            .entry(url.to_string())
+            .or_default()
+            .clone();
+        let image = {
+            let _downloading = recover(download.lock(), "image download");
+            // whoever held it before us may have just cached it
+            Self::read(&path).or_else(|_| self.download_to(url, &path))
+        };
+        let mut downloads = recover(self.downloads.lock(), "image downloads");
+        if downloads
+            .get(url)
+            .is_some_and(|current| Arc::ptr_eq(current, &download))
+        {
+            downloads.remove(url);
+        }
+        image
+    }
+
+    fn download_to(&self, url: &str, path: &Path) -> anyhow::Result<Vec<u8>> {
+        let bytes = self.download(url)?;
+        let mut size = recover(self.size.lock(), "image cache");
+        // written aside and renamed so a crash never leaves a truncated image to be served
+        let download_path = path.with_extension("download");
+        fs::write(&download_path, &bytes)?;
+        fs::rename(&download_path, path)?;
+        *size += bytes.len() as u64;
+        if *size > MAX_CACHE_BYTES {
+            match self.evict() {
+                Ok(remaining) => *size = remaining,
+                Err(e) => error!("Error evicting cached images: {}", e),
+            }
+        }
+        Ok(bytes)
+    }
+
+    /// Drops the least recently used images, returning the bytes left.
+    fn evict(&self) -> std::io::Result<u64> {
+        let files = cached_files(&self.dir)?;
+        let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+        for (_, len, path) in files.into_iter().sorted() {
+            if total <= EVICT_TO_BYTES {
+                break;
+            }
+            match fs::remove_file(&path) {
+                Ok(()) => total -= len,
+                Err(e) => error!("Error evicting cached image {:?}: {}", path, e),
+            }
+        }
+        Ok(total)
+    }
+}
+
+fn content_type(bytes: &[u8]) -> &'static str {
+    if bytes.starts_with(b"\x89PNG") {
+        "image/png"
+    } else {
+        "image/jpeg"
+    }
+}
+
+/// `convertFileSrc` percent encodes the whole remote URL into the path.
+fn percent_decode(encoded: &str) -> String {
+    let mut decoded = Vec::with_capacity(encoded.len());
+    let mut bytes = encoded.bytes();
+    while let Some(byte) = bytes.next() {
+        if byte == b'%' {
+            let hex: String = bytes.by_ref().take(2).map(char::from).collect();
+            if let Ok(byte) = u8::from_str_radix(&hex, 16) {
+                decoded.push(byte);
+                continue;
+            }
+        }
+        decoded.push(byte);
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn respond(cache: Option<&ImageCache>, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let url = percent_decode(request.uri().path().trim_start_matches('/'));
+    let image = cache
+        .ok_or_else(|| anyhow::anyhow!("image cache unavailable"))
+        .and_then(|cache| cache.get(&url));
+    let (content_type, body) = match image {
+        Ok(bytes) => (content_type(&bytes), bytes),
+        Err(e) => {
+            info!("Serving placeholder for {}: {}", url, e);
+            ("image/svg+xml", PLACEHOLDER.as_bytes().to_vec())
+        }
+    };
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, content_type)
+        .body(body)
+        .unwrap_or_default()
+}
+
+type ImageRequest = (Request<Vec<u8>>, UriSchemeResponder);
+
+/// Queue of the image requests `WORKERS` threads answer off the webview's thread.
+pub(crate) struct ImageRequests(SyncSender<ImageRequest>);
+
+pub(crate) fn start_serving(cache: &Arc<ImageCache>) -> ImageRequests {
+    let (sender, receiver) = mpsc::sync_channel(MAX_QUEUED_REQUESTS);
+    let receiver: Arc<Mutex<Receiver<ImageRequest>>> = Arc::new(Mutex::new(receiver));
+    for _ in 0..WORKERS {
+        let cache = cache.clone();
+        let receiver = receiver.clone();
+        std::thread::spawn(move || loop {
+            let next = recover(receiver.lock(), "image requests").recv();
+            let Ok((request, responder)) = next else {
+                break;
+            };
+            responder.respond(respond(Some(&cache), &request));
+        });
+    }
+    ImageRequests(sender)
+}
+
+pub(crate) fn handle_uri_scheme(
+    ctx: UriSchemeContext<'_, Wry>,
+    request: Request<Vec<u8>>,
+    responder: UriSchemeResponder,
+) {
+    let Some(requests) = ctx.app_handle().try_state::<ImageRequests>() else {
+        responder.respond(respond(None, &request));
+        return;
+    };
+    // a full queue gets the placeholder rather than blocking the webview until a worker frees up
+    if let Err(
+        TrySendError::Full((request, responder)) | TrySendError::Disconnected((request, responder)),
+    ) = requests.0.try_send((request, responder))
+    {
+        responder.respond(respond(None, &request));
+    }
+}
+
+/// Images of every card in a stored decklist.
+fn deck_image_uris(db: &mut ReadDb<'_>) -> anyhow::Result<Vec<String>> {
+    let mut card_ids = Vec::new();
+    for mtga_match in db.get_matches()? {
+        for deck in db.get_decklists(&mtga_match.id)? {
+            card_ids.extend(deck.quantities().into_keys());
+            card_ids.extend(deck.sideboard_quantities().into_keys());
+        }
+    }
+    Ok(card_ids
+        .into_iter()
+        .unique()
+        .map(|card_id| db.cards.card(card_id))
+        .map(|card| card.image_uri)
+        .filter(|image_uri| !image_uri.is_empty())
+        .unique()
+        .collect())
+}
+
+pub(crate) fn start_prefetching(cache: Arc<ImageCache>, db: DatabaseState) {
+    std::thread::spawn(move || {
+        let image_uris = db
+            .read()
+            .map_err(anyhow::Error::from)
+            .and_then(|mut db| deck_image_uris(&mut db))
+            .unwrap_or_else(|e| {
+                error!("Error listing card images to prefetch: {}", e);
+                Vec::default()
+            });
+        let missing: Vec<_> = image_uris
+            .into_iter()
+            .filter(|image_uri| !cache.contains(image_uri))
+            .collect();
+        info!("Prefetching {} card images", missing.len());
+        for image_uri in missing {
+            if let Err(e) = cache.get(&image_uri) {
+                error!("Error prefetching {}: {}", image_uri, e);
+            }
+        }
+    });
+}
@@ -1,15 +1,84 @@
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use ap_core::match_insights::MatchInsightDB;
 use ap_core::processor::{ArenaEventSource, PlayerLogProcessor};
 use ap_core::replay::MatchReplayBuilder;
 use ap_core::storage_backends::ArenaMatchStorageBackend;
+use chrono::{DateTime, Utc};
 use crossbeam_channel::{select, unbounded, Sender};
 use notify::{Event, Watcher};
+use rusqlite::Connection;
+use tauri::{AppHandle, Emitter, Manager};
 use tracing::{error, info};
 
+use crate::client_log::{ClientLogEntry, ClientLogReader};
+use crate::collection::CollectionEvent;
+use crate::current_match::{CurrentMatch, CurrentMatchState};
+use crate::database::{recover, Database, DatabaseState};
+use crate::deck_tracker::DeckTracker;
+use crate::drafts::DraftTracker;
+use crate::event_runs::{self, EventRunEvent};
+use crate::game_state::GameStateTracker;
+use crate::match_format::MatchFormat;
+use crate::match_trash;
+use crate::ranks::RankEvent;
+use crate::search;
+use crate::unknown_cards;
+
+fn set_overlay_visible(app: &AppHandle, visible: bool) {
+    if let Some(overlay) = app.get_webview_window("overlay") {
+        let result = if visible {
+            overlay.show()
+        } else {
+            overlay.hide()
+        };
+        if let Err(e) = result {
+            error!("Error toggling overlay window: {}", e);
+        }
+    }
+}
+
+fn publish_game_state(
+    app: &AppHandle,
+    db: &Database,
+    current_match: &CurrentMatchState,
+    game_state_tracker: &GameStateTracker,
+) {
+    if game_state_tracker.is_match_complete() {
+        *recover(current_match.lock(), "current match") = None;
+        if let Err(e) = app.emit("current-match-changed", None::<CurrentMatch>) {
+            error!("Error publishing current match: {}", e);
+        }
+        return;
+    }
+
+    let (snapshot, deck_tracker) = {
+        let cards = db.cards();
+        (
+            CurrentMatch::from_game_state(game_state_tracker, &cards),
+            DeckTracker::from_game_state(game_state_tracker, &cards),
+        )
+    };
+    *recover(current_match.lock(), "current match") = Some(snapshot.clone());
+    if let Err(e) = app.emit("current-match-changed", Some(snapshot)) {
+        error!("Error publishing current match: {}", e);
+    }
+    if let Err(e) = app.emit("deck-tracker-updated", deck_tracker) {
+        error!("Error publishing deck tracker: {}", e);
+    }
+    if !game_state_tracker.is_game_over() {
+        set_overlay_visible(app, true);
+    }
+}
+
+/// Replaying Player.log comes across matches deleted since, which aren't recorded again.
+fn is_deleted(conn: &Connection, match_id: &str) -> bool {
+    match_trash::is_deleted(conn, match_id).unwrap_or_else(|e| {
+        error!("Error looking up deleted matches: {}", e);
+        false
+    })
+}
+
 fn watch_player_log_rotation(notify_tx: Sender<Event>, player_log_path: &Path) {
     let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| match res {
         Ok(event) => {
@@ -28,10 +97,145 @@ fn watch_player_log_rotation(notify_tx: Sender<Event>, player_log_path: &Path) {
     }
 }
 
-fn log_process_start(db: Arc<Mutex<MatchInsightDB>>, player_log_path: &Path) {
+/// Everything ingested from `ClientLogReader` rather than `ap_core`'s `PlayerLogProcessor`.
+#[derive(Default)]
+struct ClientLogHandler {
+    game_state_tracker: GameStateTracker,
+    draft_tracker: DraftTracker,
+    linked_match_id: String,
+    /// The match in progress when it was deleted already, checked once per match.
+    deleted_match_id: String,
+    recorded_match_format: (String, MatchFormat),
+}
+
+impl ClientLogHandler {
+    /// `logged_at` is when the client logged `entry`, so replaying a log records the same times.
+    fn handle(&mut self, entry: &ClientLogEntry, logged_at: DateTime<Utc>, db: &Database) {
+        match entry {
+            ClientLogEntry::Message(message) => {
+                if let Some(game_draws) = self.game_state_tracker.ingest_message(message) {
+                    let mut db = db.write();
+                    if is_deleted(&db.conn, &game_draws.match_id) {
+                        info!(
+                            "Skipping game draws of deleted match {}",
+                            game_draws.match_id
+                        );
+                    } else if let Err(e) = game_draws.write(&db.conn) {
+                        error!("Error writing game draws to db: {}", e);
+                    } else if let Err(e) = search::index_match(&mut db, &game_draws.match_id) {
+                        error!("Error indexing match for search: {}", e);
+                    }
+                }
+                self.record_match_event(db);
+            }
+            ClientLogEntry::Request { .. } | ClientLogEntry::Response { .. } => {
+                let draft_event = self.draft_tracker.ingest_entry(entry);
+                let event_run_events = EventRunEvent::from_entry(entry);
+                let rank_event = RankEvent::from_entry(entry);
+                let collection_events = CollectionEvent::from_entry(entry);
+                if draft_event.is_none()
+                    && event_run_events.is_empty()
+                    && rank_event.is_none()
+                    && collection_events.is_empty()
+                {
+                    return;
+                }
+                let records_pool = draft_event.is_some()
+                    || !event_run_events.is_empty()
+                    || !collection_events.is_empty();
+                let mut db = db.write();
+                if let Some(draft_event) = draft_event {
+                    if let Err(e) = draft_event.write(&db.conn, logged_at) {
+                        error!("Error writing draft pick to db: {}", e);
+                    }
+                }
+                for event_run_event in event_run_events {
+                    if let Err(e) = event_run_event.write(&db.conn, logged_at) {
+                        error!("Error writing event run to db: {}", e);
+                    }
+                }
+                if let Some(rank_event) = rank_event {
+                    let match_id = Some(self.game_state_tracker.match_id())
+                        .filter(|match_id| !match_id.is_empty());
+                    if let Err(e) = rank_event.write(&db.conn, logged_at, match_id) {
+                        error!("Error writing rank to db: {}", e);
+                    }
+                }
+                for collection_event in collection_events {
+                    if let Err(e) = collection_event.write(&mut db.conn, logged_at) {
+                        error!("Error writing collection to db: {}", e);
+                    }
+                }
+                if records_pool {
+                    if let Err(e) = unknown_cards::record_pools(&db.conn, &db.cards) {
+                        error!("Error recording unknown cards: {}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Links the match in progress to its event run and records the queue it was played in.
+    fn record_match_event(&mut self, db: &Database) {
+        let match_id = self.game_state_tracker.match_id();
+        let event_id = self.game_state_tracker.event_id();
+        if event_id.is_empty() || match_id == self.deleted_match_id {
+            return;
+        }
+        if match_id != self.linked_match_id && is_deleted(&db.write().conn, match_id) {
+            info!("Skipping deleted match {}", match_id);
+            self.deleted_match_id = match_id.to_string();
+            return;
+        }
+
+        if match_id != self.linked_match_id {
+            let db = db.write();
+            if let Err(e) = event_runs::link_match(&db.conn, event_id, match_id) {
+                error!("Error linking match to event run: {}", e);
+            }
+            self.linked_match_id = match_id.to_string();
+        }
+
+        let match_format =
+            MatchFormat::new(event_id, self.game_state_tracker.match_win_condition());
+        if (match_id, &match_format)
+            != (
+                self.recorded_match_format.0.as_str(),
+                &self.recorded_match_format.1,
+            )
+        {
+            let mut db = db.write();
+            if let Err(e) = match_format.write(&db.conn, match_id) {
+                error!("Error writing match format to db: {}", e);
+            } else if let Err(e) = search::index_match(&mut db, match_id) {
+                error!("Error indexing match for search: {}", e);
+            }
+            self.recorded_match_format = (match_id.to_string(), match_format);
+        }
+    }
+}
+
+fn open_player_log(
+    player_log_path: &Path,
+) -> anyhow::Result<(PlayerLogProcessor, ClientLogReader)> {
+    Ok((
+        PlayerLogProcessor::try_new(player_log_path.into())?,
+        ClientLogReader::try_new(player_log_path)?,
+    ))
+}
+
+fn log_process_start(
+    app: AppHandle,
+    db: DatabaseState,
+    current_match: CurrentMatchState,
+    player_log_path: &Path,
+) {
     let (notify_tx, notify_rx) = unbounded::<Event>();
-    let mut processor = PlayerLogProcessor::try_new(player_log_path.into())
-        .expect("Could not build player log processor");
+    let (mut processor, mut client_log) =
+        open_player_log(player_log_path).expect("Could not open player log");
+    let mut client_log_handler = ClientLogHandler::default();
+    // set when the log was rotated until it could be opened again
+    let mut reopen = false;
     let mut match_replay_builder = MatchReplayBuilder::new();
     info!("Player log: {:?}", player_log_path);
     let plp = player_log_path.to_owned().clone();
@@ -45,19 +249,36 @@ fn log_process_start(db: Arc<Mutex<MatchInsightDB>>, player_log_path: &Path) {
             recv(notify_rx) -> event => {
                 if let Ok(event) = event {
                     info!("log file rotated!, {:?}", event);
-                    processor = PlayerLogProcessor::try_new(player_log_path.into())
-                        .expect("Could not build player log processor");
+                    reopen = true;
                 }
             }
             default(Duration::from_secs(1)) => {
+                if reopen {
+                    match open_player_log(player_log_path) {
+                        Ok(reopened) => {
+                            (processor, client_log) = reopened;
+                            reopen = false;
+                        }
+                        Err(e) => error!("Error reopening player log, retrying: {}", e),
+                    }
+                }
                 while let Some(parse_output) = processor.get_next_event() {
                     if match_replay_builder.ingest_event(parse_output) {
                         let match_replay = match_replay_builder.build();
                         match match_replay {
                             Ok(mr) => {
-                                let mut db = db.lock().expect("Could not lock db");
-                                if let Err(e) = db.write(&mr) {
+                                let mut db = db.write();
+                                if is_deleted(&db.conn, &mr.match_id) {
+                                    info!("Skipping deleted match {}", mr.match_id);
+                                } else if let Err(e) = db.write(&mr) {
                                     error!("Error writing match to db: {}", e);
+                                } else if let Err(e) =
+                                    unknown_cards::record_match(&db.conn, &db.cards, &mr.match_id)
+                                {
+                                    error!("Error recording unknown cards: {}", e);
+                                }
+                                if let Err(e) = search::index_match(&mut db, &mr.match_id) {
+                                    error!("Error indexing match for search: {}", e);
                                 }
                             }
                             Err(e) => {
@@ -65,15 +286,101 @@ fn log_process_start(db: Arc<Mutex<MatchInsightDB>>, player_log_path: &Path) {
                             }
                         }
                         match_replay_builder = MatchReplayBuilder::new();
+                        set_overlay_visible(&app, false);
                     }
                 }
+                while let Some(entry) = client_log.next_entry() {
+                    let logged_at = client_log.logged_at().unwrap_or_else(Utc::now);
+                    client_log_handler.handle(&entry, logged_at, &db);
+                }
+                if client_log_handler.game_state_tracker.take_changed() {
+                    publish_game_state(
+                        &app,
+                        &db,
+                        &current_match,
+                        &client_log_handler.game_state_tracker,
+                    );
+                }
             }
         }
     }
 }
 
-pub fn start_processing_logs(db: Arc<Mutex<MatchInsightDB>>, player_log_path: PathBuf) {
+pub fn start_processing_logs(
+    app: AppHandle,
+    db: DatabaseState,
+    current_match: CurrentMatchState,
+    player_log_path: PathBuf,
+) {
     std::thread::spawn(move || {
-        log_process_start(db, &player_log_path);
+        log_process_start(app, db, current_match, &player_log_path);
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fs;
+
+    use ap_core::cards::CardsDatabase;
+    use serde_json::json;
+
+    use super::*;
+    use crate::cards::Cards;
+    use crate::database::testing::{insert_match, migrated};
+
+    #[test]
+    fn skips_matches_deleted_before_the_log_is_replayed() {
+        let path =
+            std::env::temp_dir().join(format!("arena-buddy-{}-ingest.db", std::process::id()));
+        let mut db = migrated(Connection::open(&path).expect("Could not open database"));
+        insert_match(&db.conn, "a", "them");
+        let batch =
+            match_trash::delete(&mut db.conn, &["a".to_string()]).expect("Could not delete");
+        let cards = Cards::new(
+            CardsDatabase {
+                db: HashMap::default(),
+            },
+            HashMap::default(),
+        );
+        let db = Database::new(path.clone(), db, cards).expect("Could not open database");
+
+        let game_over = json!({
+            "greToClientEvent": {
+                "greToClientMessages": [{
+                    "systemSeatIds": [1],
+                    "gameStateMessage": {
+                        "gameInfo": {
+                            "matchID": "a",
+                            "gameNumber": 1,
+                            "stage": "GameStage_GameOver"
+                        },
+                        "gameObjects": [{
+                            "instanceId": 5,
+                            "grpId": 67330,
+                            "ownerSeatId": 2,
+                            "type": "GameObjectType_Card",
+                            "visibility": "Visibility_Public"
+                        }]
+                    }
+                }]
+            }
+        });
+        ClientLogHandler::default().handle(&ClientLogEntry::Message(game_over), Utc::now(), &db);
+
+        let recorded: i64 = db
+            .write()
+            .conn
+            .query_row(
+                "SELECT COUNT(*) FROM opponent_cards WHERE match_id = 'a'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("Could not count opponent cards");
+        assert_eq!(recorded, 0);
+        match_trash::restore(&mut db.write().conn, batch.id).expect("Could not restore");
+
+        drop(db);
+        fs::remove_file(&path).expect("Could not remove test database");
+    }
+}
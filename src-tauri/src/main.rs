@@ -7,6 +7,7 @@
 #![allow(clippy::too_many_arguments)]
 #![allow(clippy::needless_pass_by_value)]
 
+use std::collections::HashMap;
 use std::error::Error;
 use std::fmt::Display;
 use std::sync::{Arc, Mutex};
@@ -16,31 +17,63 @@ use ap_core::match_insights::MatchInsightDB;
 use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
 use tauri::{path::BaseDirectory, App, Manager};
-use tracing::{info, Level};
+use tracing::{error, info, warn, Level};
 use tracing_appender::rolling::{RollingFileAppender, Rotation};
 use tracing_subscriber::fmt::writer::MakeWriterExt;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 
+use crate::backups::BackupReason;
+use crate::card_search::{CardIndex, CardIndexState};
+use crate::cards::Cards;
+use crate::current_match::CurrentMatchState;
+use crate::database::{Database, DatabaseState};
+use crate::image_cache::ImageCache;
+
+mod annotations;
+mod backups;
 mod card;
+mod card_import;
+mod card_metadata;
+mod card_search;
+mod cards;
+mod client_log;
+mod collection;
 mod commands;
+mod current_match;
+mod database;
 mod deck;
+mod deck_tracker;
+mod decklist;
+mod drafts;
+mod event_runs;
+mod game_state;
+mod image_cache;
 mod ingest;
+mod match_format;
+mod match_trash;
+mod ranks;
+mod schema;
+mod search;
+mod unknown_cards;
 
 #[derive(Debug, Deserialize, Serialize)]
 pub enum ArenaBuddySetupError {
+    BackupFailure,
     CorruptedAppData,
     LogSetupFailure,
     MatchesDatabaseInitializationFailure,
     NoCardsDatabase,
     NoHomeDir,
     NoMathchesDatabase,
+    SchemaMigrationFailure,
     UnsupportedOS,
 }
 
 impl Display for ArenaBuddySetupError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            Self::BackupFailure => write!(f, "Could not back up the matches database"),
             Self::CorruptedAppData => write!(f, "App data is corrupted"),
             Self::LogSetupFailure => write!(f, "Could not setup logging"),
             Self::MatchesDatabaseInitializationFailure => {
@@ -49,6 +82,7 @@ impl Display for ArenaBuddySetupError {
             Self::NoCardsDatabase => write!(f, "Cards database not found"),
             Self::NoHomeDir => write!(f, "Home directory not found"),
             Self::NoMathchesDatabase => write!(f, "Matches database not found"),
+            Self::SchemaMigrationFailure => write!(f, "Could not migrate the matches database"),
             Self::UnsupportedOS => write!(f, "Unsupported operating system"),
         }
     }
@@ -84,18 +118,61 @@ fn setup(app: &mut App) -> Result<(), Box<dyn Error>> {
         .map_err(|_| ArenaBuddySetupError::NoCardsDatabase)?;
     info!("cards_db path: {:?}", cards_path);
     let cards_db =
-        CardsDatabase::new(cards_path).map_err(|_| ArenaBuddySetupError::NoCardsDatabase)?;
+        CardsDatabase::new(&cards_path).map_err(|_| ArenaBuddySetupError::NoCardsDatabase)?;
+    let metadata = card_metadata::load(&cards_path).unwrap_or_else(|e| {
+        warn!("Could not load card metadata: {}", e);
+        HashMap::default()
+    });
+    let mut cards = Cards::new(cards_db, metadata);
+
+    let overlay = card_import::read_overlay(&card_import::overlay_path(&app_data_dir))
+        .and_then(|overlay| card_import::apply(&mut cards, overlay.values()));
+    match overlay {
+        Ok(newly_resolved) => info!("Cards overlay added {} cards", newly_resolved),
+        Err(e) => warn!("Could not load cards overlay: {}", e),
+    }
+
+    let card_index = CardIndexState::new(Mutex::new(CardIndex::new(&cards)));
 
-    let ruby = cards_db.get("93958");
+    let ruby = cards.get(93958);
     info!("Ruby: {:?}", ruby);
 
     let db_path = app_data_dir.join("matches.db");
     info!("Database path: {}", db_path.to_string_lossy());
-    let conn = Connection::open(db_path).map_err(|_| ArenaBuddySetupError::NoMathchesDatabase)?;
-    let mut db = MatchInsightDB::new(conn, cards_db);
+    let db_existed = db_path.exists();
+    let conn = Connection::open(&db_path).map_err(|_| ArenaBuddySetupError::NoMathchesDatabase)?;
+    let mut db = MatchInsightDB::new(conn, cards.copy_database());
+    let backups_dir = backups::backups_dir(&app_data_dir);
+    if db_existed
+        && schema::has_pending_migrations(&db.conn)
+            .map_err(|_| ArenaBuddySetupError::SchemaMigrationFailure)?
+    {
+        backups::create(&db.conn, &backups_dir, BackupReason::PreMigration).map_err(|e| {
+            error!("Pre-migration backup failed: {}", e);
+            ArenaBuddySetupError::BackupFailure
+        })?;
+    }
     db.init()
         .map_err(|_| ArenaBuddySetupError::MatchesDatabaseInitializationFailure)?;
-    let db_arc = Arc::new(Mutex::new(db));
+    schema::migrate(&mut db.conn).map_err(|e| {
+        error!("Schema migration failed: {}", e);
+        ArenaBuddySetupError::SchemaMigrationFailure
+    })?;
+    if let Err(e) = match_trash::purge(&mut db.conn) {
+        error!("Error purging the trash: {}", e);
+    }
+    let database = DatabaseState::new(
+        Database::new(db_path, db, cards)
+            .map_err(|_| ArenaBuddySetupError::MatchesDatabaseInitializationFailure)?,
+    );
+    backups::start_scheduled_backups(database.clone(), backups_dir);
+
+    let image_cache = Arc::new(
+        ImageCache::new(app_data_dir.join("images"))
+            .map_err(|_| ArenaBuddySetupError::CorruptedAppData)?,
+    );
+    image_cache::start_prefetching(image_cache.clone(), database.clone());
+    search::start_indexing(database.clone());
 
     let home = app
         .path()
@@ -108,21 +185,70 @@ fn setup(app: &mut App) -> Result<(), Box<dyn Error>> {
         _ => Err(ArenaBuddySetupError::UnsupportedOS),
     }?;
 
-    app.manage(db_arc.clone());
+    app.manage(database.clone());
+    app.manage(image_cache::start_serving(&image_cache));
+    app.manage(image_cache);
+    app.manage(card_index);
+    let current_match = CurrentMatchState::default();
+    app.manage(current_match.clone());
     info!(
         "Processing logs from : {}",
         player_log_path.to_string_lossy()
     );
-    ingest::start_processing_logs(db_arc.clone(), player_log_path);
+    ingest::start_processing_logs(
+        app.handle().clone(),
+        database,
+        current_match,
+        player_log_path,
+    );
     Ok(())
 }
 
 fn main() {
     tauri::Builder::default()
         .setup(setup)
+        .register_asynchronous_uri_scheme_protocol(
+            image_cache::URI_SCHEME,
+            image_cache::handle_uri_scheme,
+        )
         .invoke_handler(tauri::generate_handler![
             commands::matches::command_matches,
-            commands::match_details::command_match_details
+            commands::match_details::command_match_details,
+            commands::card_stats::command_card_stats,
+            commands::current_match::command_current_match,
+            commands::drafts::command_drafts,
+            commands::draft_details::command_draft_details,
+            commands::event_runs::command_event_runs,
+            commands::event_run_details::command_event_run_details,
+            commands::match_stats::command_match_stats,
+            commands::rank_history::command_rank_history,
+            commands::collection::command_collection,
+            commands::collection::command_collection_snapshots,
+            commands::collection::command_collection_diff,
+            commands::wildcard_cost::command_wildcard_cost,
+            commands::import_decklist::command_import_decklist,
+            commands::deck_diff::command_deck_diff,
+            commands::import_cards::command_import_cards,
+            commands::unknown_cards::command_unknown_cards,
+            commands::unknown_cards::command_resolve_unknown_cards,
+            commands::search_cards::command_search_cards,
+            commands::backups::command_backups,
+            commands::backups::command_create_backup,
+            commands::backups::command_restore_backup,
+            commands::manage_matches::command_select_matches,
+            commands::manage_matches::command_delete_matches,
+            commands::manage_matches::command_merge_matches,
+            commands::manage_matches::command_hide_matches,
+            commands::manage_matches::command_trash,
+            commands::manage_matches::command_restore_trash,
+            commands::annotations::command_annotations,
+            commands::annotations::command_add_note,
+            commands::annotations::command_edit_note,
+            commands::annotations::command_remove_note,
+            commands::annotations::command_add_tag,
+            commands::annotations::command_remove_tag,
+            commands::annotations::command_tags,
+            commands::search::command_search
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
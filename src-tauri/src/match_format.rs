@@ -0,0 +1,178 @@
+use std::collections::{BTreeSet, HashMap};
+
+use indoc::indoc;
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+/// The queue a match was played in, captured from the match start messages.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct MatchFormat {
+    pub event_id: String,
+    pub format: String,
+    pub best_of: Option<i32>,
+}
+
+impl MatchFormat {
+    pub fn new(event_id: &str, match_win_condition: &str) -> Self {
+        let best_of = match match_win_condition {
+            "MatchWinCondition_SingleElimination" => Some(1),
+            "MatchWinCondition_Best2of3" => Some(3),
+            _ => None,
+        };
+        Self {
+            event_id: event_id.to_string(),
+            format: format_from_event_id(event_id).to_string(),
+            best_of,
+        }
+    }
+
+    pub fn write(&self, conn: &Connection, match_id: &str) -> rusqlite::Result<()> {
+        conn.execute(
+            indoc! {r"
+                INSERT INTO match_formats (match_id, event_id, format, best_of)
+                VALUES (?1, ?2, ?3, ?4)
+                ON CONFLICT (match_id) DO UPDATE SET
+                    event_id = excluded.event_id,
+                    format = excluded.format,
+                    best_of = COALESCE(excluded.best_of, best_of)
+            "},
+            (match_id, &self.event_id, &self.format, self.best_of),
+        )?;
+        Ok(())
+    }
+
+    pub fn get(conn: &Connection, match_id: &str) -> rusqlite::Result<Option<Self>> {
+        conn.query_row(
+            "SELECT event_id, format, best_of FROM match_formats WHERE match_id = ?1",
+            [match_id],
+            |row| {
+                Ok(Self {
+                    event_id: row.get(0)?,
+                    format: row.get(1)?,
+                    best_of: row.get(2)?,
+                })
+            },
+        )
+        .optional()
+    }
+
+    pub fn get_all(conn: &Connection) -> rusqlite::Result<HashMap<String, Self>> {
+        let mut statement =
+            conn.prepare("SELECT match_id, event_id, format, best_of FROM match_formats")?;
+        let rows = statement.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                Self {
+                    event_id: row.get(1)?,
+                    format: row.get(2)?,
+                    best_of: row.get(3)?,
+                },
+            ))
+        })?;
+        rows.collect()
+    }
+}
+
+/// Arena event ids look like `Ladder`, `Traditional_Historic_Ladder` or
+/// `PremierDraft_MKM_20240206`.
+pub(crate) fn format_from_event_id(event_id: &str) -> &'static str {
+    let event_id = event_id.to_ascii_lowercase();
+    if event_id.contains("draft") {
+        "Draft"
+    } else if event_id.contains("sealed") {
+        "Sealed"
+    } else if event_id.contains("brawl") {
+        "Brawl"
+    } else if event_id.contains("historic") {
+        "Historic"
+    } else if event_id.contains("explorer") || event_id.contains("pioneer") {
+        "Explorer"
+    } else if event_id.contains("timeless") {
+        "Timeless"
+    } else if event_id.contains("alchemy") {
+        "Alchemy"
+    } else if event_id.contains("ladder")
+        || event_id.contains("standard")
+        // the unqualified constructed queues, `Play` and `Constructed_BestOf3`, are Standard
+        || event_id.contains("constructed")
+        || event_id == "play"
+    {
+        "Standard"
+    } else {
+        "Unknown"
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub(crate) enum MatchGrouping {
+    EventId,
+    Format,
+    BestOf,
+}
+
+impl MatchGrouping {
+    pub fn key(self, match_format: Option<&MatchFormat>) -> String {
+        let Some(match_format) = match_format else {
+            return "Unknown".to_string();
+        };
+        match self {
+            Self::EventId => match_format.event_id.clone(),
+            Self::Format => match_format.format.clone(),
+            Self::BestOf => match_format
+                .best_of
+                .map_or_else(|| "Unknown".to_string(), |best_of| format!("Bo{best_of}")),
+        }
+    }
+}
+
+/// Optional restrictions shared by the list and statistics commands.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct MatchFilter {
+    pub event_id: Option<String>,
+    pub format: Option<String>,
+    pub best_of: Option<i32>,
+    /// Tags a match has to carry, all of them.
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl MatchFilter {
+    pub fn accepts(&self, match_format: Option<&MatchFormat>) -> bool {
+        let unrestricted =
+            self.event_id.is_none() && self.format.is_none() && self.best_of.is_none();
+        match match_format {
+            None => unrestricted,
+            Some(match_format) => {
+                self.event_id
+                    .as_ref()
+                    .map_or(true, |event_id| *event_id == match_format.event_id)
+                    && self
+                        .format
+                        .as_ref()
+                        .map_or(true, |format| *format == match_format.format)
+                    && self
+                        .best_of
+                        .map_or(true, |best_of| Some(best_of) == match_format.best_of)
+            }
+        }
+    }
+
+    /// For event runs and drafts, which have an event but no best-of or tags of their own.
+    pub fn accepts_event(&self, event_id: &str) -> bool {
+        self.event_id.as_ref().map_or(true, |id| id == event_id)
+            && self
+                .format
+                .as_ref()
+                .map_or(true, |format| format == format_from_event_id(event_id))
+    }
+
+    /// Whether a match with `match_tags` carries every tag asked for. Tags are trimmed like they
+    /// are when added, blank ones restrict nothing.
+    pub fn accepts_tags(&self, match_tags: Option<&BTreeSet<String>>) -> bool {
+        self.tags
+            .iter()
+            .map(|tag| tag.trim())
+            .filter(|tag| !tag.is_empty())
+            .all(|tag| match_tags.is_some_and(|match_tags| match_tags.contains(tag)))
+    }
+}
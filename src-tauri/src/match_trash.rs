@@ -0,0 +1,559 @@
+use std::collections::HashSet;
+
+use anyhow::bail;
+use chrono::{DateTime, Utc};
+use indoc::indoc;
+use itertools::Itertools;
+use rusqlite::types::Value;
+use rusqlite::{params_from_iter, Connection, OptionalExtension, Transaction};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use crate::annotations;
+use crate::commands::error::CommandError;
+
+/// Every table holding rows of a match, with the column naming it. `rank_snapshots` stays put:
+/// a snapshot describes the player's rank after the match, which deleting it doesn't undo.
+const MATCH_TABLES: &[(&str, &str)] = &[
+    ("matches", "id"),
+    ("decklists", "match_id"),
+    ("mulligans", "match_id"),
+    ("match_results", "match_id"),
+    ("game_draws", "match_id"),
+    ("opponent_cards", "match_id"),
+    ("event_run_matches", "match_id"),
+    ("match_formats", "match_id"),
+    ("unknown_cards", "match_id"),
+    ("hidden_matches", "match_id"),
+    ("match_notes", "match_id"),
+    ("match_tags", "match_id"),
+];
+
+/// The tables arena-buddy owns, whose keys include the match id so rows copied onto another
+/// match can't duplicate what it already has.
+const MERGED_TABLES: &[&str] = &[
+    "game_draws",
+    "opponent_cards",
+    "event_run_matches",
+    "match_formats",
+    "unknown_cards",
+    "match_tags",
+];
+
+/// Deleted matches can be restored for this long.
+const TRASH_RETENTION: chrono::Duration = chrono::Duration::days(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum TrashAction {
+    Delete,
+    Merge,
+}
+
+impl TrashAction {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Delete => "delete",
+            Self::Merge => "merge",
+        }
+    }
+
+    fn parse(action: &str) -> Option<Self> {
+        match action {
+            "delete" => Some(Self::Delete),
+            "merge" => Some(Self::Merge),
+            _ => None,
+        }
+    }
+}
+
+/// Matches removed together, restored together.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct TrashBatch {
+    pub id: i64,
+    pub action: TrashAction,
+    pub deleted_at: DateTime<Utc>,
+    pub match_ids: Vec<String>,
+}
+
+/// A column value as stored in `trash_rows.row_data`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+enum StoredValue {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+impl From<Value> for StoredValue {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Null => Self::Null,
+            Value::Integer(value) => Self::Integer(value),
+            Value::Real(value) => Self::Real(value),
+            Value::Text(value) => Self::Text(value),
+            Value::Blob(value) => Self::Blob(value),
+        }
+    }
+}
+
+impl From<StoredValue> for Value {
+    fn from(value: StoredValue) -> Self {
+        match value {
+            StoredValue::Null => Self::Null,
+            StoredValue::Integer(value) => Self::Integer(value),
+            StoredValue::Real(value) => Self::Real(value),
+            StoredValue::Text(value) => Self::Text(value),
+            StoredValue::Blob(value) => Self::Blob(value),
+        }
+    }
+}
+
+/// Column name and value pairs, so rows survive columns `ap_core` adds later.
+type StoredRow = Vec<(String, StoredValue)>;
+
+fn select_rows(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    match_id: &str,
+) -> rusqlite::Result<Vec<StoredRow>> {
+    let mut statement = conn.prepare(&format!("SELECT * FROM {table} WHERE {column} = ?1"))?;
+    let columns: Vec<String> = statement
+        .column_names()
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    let rows = statement.query_map([match_id], |row| {
+        columns
+            .iter()
+            .enumerate()
+            .map(|(index, column)| Ok((column.clone(), row.get::<_, Value>(index)?.into())))
+            .collect()
+    })?;
+    rows.collect()
+}
+
+fn insert_row(
+    conn: &Connection,
+    table: &str,
+    row: StoredRow,
+    or_ignore: bool,
+) -> rusqlite::Result<usize> {
+    let columns = row
+        .iter()
+        .map(|(column, _)| format!("\"{column}\""))
+        .join(", ");
+    let placeholders = (1..=row.len()).map(|index| format!("?{index}")).join(", ");
+    let or_ignore = if or_ignore { " OR IGNORE" } else { "" };
+    conn.execute(
+        &format!("INSERT{or_ignore} INTO {table} ({columns}) VALUES ({placeholders})"),
+        params_from_iter(row.into_iter().map(|(_, value)| Value::from(value))),
+    )
+}
+
+fn match_exists(conn: &Connection, match_id: &str) -> rusqlite::Result<bool> {
+    Ok(conn
+        .query_row(
+            "SELECT 1 FROM matches WHERE id = ?1",
+            [match_id],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some())
+}
+
+/// Whether `match_id` was deleted or merged away and not restored since, even once purged.
+pub(crate) fn is_deleted(conn: &Connection, match_id: &str) -> rusqlite::Result<bool> {
+    Ok(conn
+        .query_row(
+            "SELECT 1 FROM deleted_matches WHERE match_id = ?1",
+            [match_id],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some())
+}
+
+fn start_batch(tx: &Transaction<'_>, action: TrashAction) -> rusqlite::Result<TrashBatch> {
+    let deleted_at = Utc::now();
+    tx.execute(
+        "INSERT INTO trash_batches (action, deleted_at) VALUES (?1, ?2)",
+        (action.as_str(), deleted_at),
+    )?;
+    Ok(TrashBatch {
+        id: tx.last_insert_rowid(),
+        action,
+        deleted_at,
+        match_ids: Vec::new(),
+    })
+}
+
+/// Moves every row of `match_id` into the trash under `batch`.
+fn trash_match(tx: &Transaction<'_>, batch: &mut TrashBatch, match_id: &str) -> anyhow::Result<()> {
+    if !match_exists(tx, match_id)? {
+        return Err(CommandError::not_found(format!("match {match_id}")).into());
+    }
+    for (table, column) in MATCH_TABLES {
+        for row in select_rows(tx, table, column, match_id)? {
+            tx.execute(
+                indoc! {r"
+                    INSERT INTO trash_rows (batch_id, match_id, source_table, row_data)
+                    VALUES (?1, ?2, ?3, ?4)
+                "},
+                (batch.id, match_id, table, serde_json::to_string(&row)?),
+            )?;
+        }
+        tx.execute(
+            &format!("DELETE FROM {table} WHERE {column} = ?1"),
+            [match_id],
+        )?;
+    }
+    tx.execute(
+        "INSERT OR REPLACE INTO deleted_matches (match_id, deleted_at) VALUES (?1, ?2)",
+        (match_id, batch.deleted_at),
+    )?;
+    batch.match_ids.push(match_id.to_string());
+    Ok(())
+}
+
+/// Moves the matches and everything recorded about them to the trash, in one transaction.
+pub(crate) fn delete(conn: &mut Connection, match_ids: &[String]) -> anyhow::Result<TrashBatch> {
+    if match_ids.is_empty() {
+        bail!("no matches to delete");
+    }
+    let tx = conn.transaction()?;
+    let mut batch = start_batch(&tx, TrashAction::Delete)?;
+    for match_id in match_ids.iter().unique() {
+        trash_match(&tx, &mut batch, match_id)?;
+    }
+    tx.commit()?;
+    info!("Moved {} matches to the trash", batch.match_ids.len());
+    // the matches are in the trash either way, old batches go on the next delete
+    if let Err(e) = purge(conn) {
+        error!("Error purging the trash: {}", e);
+    }
+    Ok(batch)
+}
+
+/// Folds `duplicate_ids` into `keep_id`: rows of arena-buddy's own tables the kept match lacks
+/// and the duplicates' notes are copied onto it, then the duplicates go to the trash.
+/// `ap_core`'s rows of the kept match win, restoring the batch brings the duplicates back
+/// untouched.
+pub(crate) fn merge(
+    conn: &mut Connection,
+    keep_id: &str,
+    duplicate_ids: &[String],
+) -> anyhow::Result<TrashBatch> {
+    if duplicate_ids.iter().all(|id| id == keep_id) {
+        bail!("no duplicates of match {keep_id} to merge");
+    }
+    let tx = conn.transaction()?;
+    if !match_exists(&tx, keep_id)? {
+        return Err(CommandError::not_found(format!("match {keep_id}")).into());
+    }
+    let mut batch = start_batch(&tx, TrashAction::Merge)?;
+    for duplicate_id in duplicate_ids.iter().unique().filter(|id| *id != keep_id) {
+        for table in MERGED_TABLES {
+            for row in select_rows(&tx, table, "match_id", duplicate_id)? {
+                let row = row
+                    .into_iter()
+                    .map(|(column, value)| match column.as_str() {
+                        "match_id" => (column, StoredValue::Text(keep_id.to_string())),
+                        _ => (column, value),
+                    })
+                    .collect();
+                insert_row(&tx, table, row, true)?;
+            }
+        }
+        annotations::copy_notes(&tx, duplicate_id, keep_id)?;
+        trash_match(&tx, &mut batch, duplicate_id)?;
+    }
+    tx.commit()?;
+    info!("Merged {} matches into {}", batch.match_ids.len(), keep_id);
+    Ok(batch)
+}
+
+/// Puts a batch's rows back where they came from and empties it from the trash. Restoring a
+/// merge leaves the rows, tags and notes it copied onto the kept match in place.
+pub(crate) fn restore(conn: &mut Connection, batch_id: i64) -> anyhow::Result<Vec<String>> {
+    let tx = conn.transaction()?;
+    let rows: Vec<(String, String, String)> = {
+        let mut statement = tx.prepare(indoc! {r"
+            SELECT match_id, source_table, row_data FROM trash_rows
+            WHERE batch_id = ?1 ORDER BY rowid
+        "})?;
+        let rows = statement.query_map([batch_id], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+    if rows.is_empty() {
+        return Err(CommandError::not_found(format!("trash batch {batch_id}")).into());
+    }
+
+    let match_ids: Vec<String> = rows
+        .iter()
+        .map(|(match_id, _, _)| match_id.clone())
+        .unique()
+        .collect();
+    for match_id in &match_ids {
+        // ap_core's tables have no keys to catch a second copy of the match's rows.
+        if match_exists(&tx, match_id)? {
+            bail!("match {match_id} was recorded again since it was deleted");
+        }
+    }
+    for match_id in &match_ids {
+        tx.execute(
+            "DELETE FROM deleted_matches WHERE match_id = ?1",
+            [match_id],
+        )?;
+    }
+    for (_, table, row_data) in rows {
+        if !MATCH_TABLES.iter().any(|(known, _)| *known == table) {
+            bail!("unknown table {table} in the trash");
+        }
+        insert_row(&tx, &table, serde_json::from_str(&row_data)?, false)?;
+    }
+    tx.execute("DELETE FROM trash_rows WHERE batch_id = ?1", [batch_id])?;
+    tx.execute("DELETE FROM trash_batches WHERE id = ?1", [batch_id])?;
+    tx.commit()?;
+    info!("Restored {} matches from the trash", match_ids.len());
+    Ok(match_ids)
+}
+
+/// Batches still in the trash, newest first.
+pub(crate) fn list(conn: &Connection) -> anyhow::Result<Vec<TrashBatch>> {
+    let mut statement = conn.prepare(indoc! {r"
+        SELECT b.id, b.action, b.deleted_at, r.match_id
+        FROM trash_batches b JOIN trash_rows r ON b.id = r.batch_id
+        GROUP BY b.id, r.match_id
+        ORDER BY b.id DESC, MIN(r.rowid)
+    "})?;
+    let rows = statement.query_map([], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, DateTime<Utc>>(2)?,
+            row.get::<_, String>(3)?,
+        ))
+    })?;
+
+    let mut batches: Vec<TrashBatch> = Vec::new();
+    for row in rows {
+        let (id, action, deleted_at, match_id) = row?;
+        match batches.last_mut() {
+            Some(batch) if batch.id == id => batch.match_ids.push(match_id),
+            _ => {
+                let Some(action) = TrashAction::parse(&action) else {
+                    bail!("unknown trash action {action}");
+                };
+                batches.push(TrashBatch {
+                    id,
+                    action,
+                    deleted_at,
+                    match_ids: vec![match_id],
+                });
+            }
+        }
+    }
+    Ok(batches)
+}
+
+/// Drops batches older than `TRASH_RETENTION` for good, their matches stay deleted.
+pub(crate) fn purge(conn: &mut Connection) -> anyhow::Result<()> {
+    let cutoff = Utc::now() - TRASH_RETENTION;
+    let tx = conn.transaction()?;
+    tx.execute(
+        indoc! {r"
+            DELETE FROM trash_rows
+            WHERE batch_id IN (SELECT id FROM trash_batches WHERE deleted_at < ?1)
+        "},
+        [cutoff],
+    )?;
+    let purged = tx.execute("DELETE FROM trash_batches WHERE deleted_at < ?1", [cutoff])?;
+    tx.commit()?;
+    if purged > 0 {
+        info!("Purged {} batches from the trash", purged);
+    }
+    Ok(())
+}
+
+/// Hides or unhides matches from the statistics commands, returning how many changed.
+pub(crate) fn set_hidden(
+    conn: &mut Connection,
+    match_ids: &[String],
+    hidden: bool,
+) -> anyhow::Result<usize> {
+    let tx = conn.transaction()?;
+    let mut changed = 0;
+    for match_id in match_ids {
+        changed += if hidden {
+            if !match_exists(&tx, match_id)? {
+                return Err(CommandError::not_found(format!("match {match_id}")).into());
+            }
+            tx.execute(
+                "INSERT OR IGNORE INTO hidden_matches (match_id, hidden_at) VALUES (?1, ?2)",
+                (match_id, Utc::now()),
+            )?
+        } else {
+            tx.execute("DELETE FROM hidden_matches WHERE match_id = ?1", [match_id])?
+        };
+    }
+    tx.commit()?;
+    Ok(changed)
+}
+
+pub(crate) fn hidden_match_ids(conn: &Connection) -> rusqlite::Result<HashSet<String>> {
+    let mut statement = conn.prepare("SELECT match_id FROM hidden_matches")?;
+    let rows = statement.query_map([], |row| row.get(0))?;
+    rows.collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::testing::{database, insert_match};
+
+    fn record_match(conn: &Connection, match_id: &str, drawn_card_id: i32) {
+        insert_match(conn, match_id, "them");
+        conn.execute(
+            indoc! {r"
+                INSERT INTO match_results (match_id, game_number, winning_team_id, result_scope)
+                VALUES (?1, 1, 1, 'MatchScope_Match')
+            "},
+            [match_id],
+        )
+        .expect("Could not insert match result");
+        conn.execute(
+            "INSERT INTO game_draws (match_id, game_number, card_id, quantity) VALUES (?1, 1, ?2, 1)",
+            (match_id, drawn_card_id),
+        )
+        .expect("Could not insert game draws");
+    }
+
+    /// Every row of the match, to compare before deleting and after restoring.
+    fn rows(conn: &Connection, match_id: &str) -> Vec<(String, String)> {
+        MATCH_TABLES
+            .iter()
+            .flat_map(|(table, column)| {
+                select_rows(conn, table, column, match_id)
+                    .expect("Could not select rows")
+                    .into_iter()
+                    .map(|row| {
+                        let row = serde_json::to_string(&row).expect("Could not serialize row");
+                        ((*table).to_string(), row)
+                    })
+            })
+            .sorted()
+            .collect()
+    }
+
+    fn drawn_card_ids(conn: &Connection, match_id: &str) -> Vec<i32> {
+        let mut statement = conn
+            .prepare("SELECT card_id FROM game_draws WHERE match_id = ?1 ORDER BY card_id")
+            .expect("Could not prepare");
+        let rows = statement
+            .query_map([match_id], |row| row.get(0))
+            .expect("Could not query game draws");
+        rows.collect::<rusqlite::Result<_>>()
+            .expect("Could not read game draws")
+    }
+
+    #[test]
+    fn restores_deleted_matches_as_they_were() {
+        let mut db = database();
+        record_match(&db.conn, "a", 67330);
+        record_match(&db.conn, "b", 67330);
+        annotations::add_note(&db.conn, "a", None, "misplayed turn 3").expect("Could not note");
+        annotations::add_tag(&db.conn, "a", "tilted").expect("Could not tag");
+        let before = rows(&db.conn, "a");
+
+        let batch = delete(&mut db.conn, &["a".to_string()]).expect("Could not delete");
+        assert_eq!(batch.action, TrashAction::Delete);
+        assert_eq!(batch.match_ids, ["a"]);
+        assert!(rows(&db.conn, "a").is_empty());
+        assert!(match_exists(&db.conn, "b").expect("Could not look up match"));
+        let trash = list(&db.conn).expect("Could not list the trash");
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].match_ids, ["a"]);
+
+        let restored = restore(&mut db.conn, batch.id).expect("Could not restore");
+        assert_eq!(restored, ["a"]);
+        assert_eq!(rows(&db.conn, "a"), before);
+        assert!(list(&db.conn).expect("Could not list the trash").is_empty());
+    }
+
+    #[test]
+    fn refuses_to_restore_a_match_recorded_again() {
+        let mut db = database();
+        record_match(&db.conn, "a", 67330);
+        let batch = delete(&mut db.conn, &["a".to_string()]).expect("Could not delete");
+        record_match(&db.conn, "a", 67330);
+
+        assert!(restore(&mut db.conn, batch.id).is_err());
+        assert_eq!(list(&db.conn).expect("Could not list the trash").len(), 1);
+    }
+
+    #[test]
+    fn keeps_deleted_matches_from_being_recorded_again() {
+        let mut db = database();
+        record_match(&db.conn, "a", 67330);
+        let batch = delete(&mut db.conn, &["a".to_string()]).expect("Could not delete");
+        assert!(is_deleted(&db.conn, "a").expect("Could not look up deleted matches"));
+        assert!(!is_deleted(&db.conn, "b").expect("Could not look up deleted matches"));
+
+        // the trash is purged long before Player.log stops replaying the match
+        db.conn
+            .execute(
+                "UPDATE trash_batches SET deleted_at = ?1",
+                [Utc::now() - TRASH_RETENTION * 2],
+            )
+            .expect("Could not age the trash");
+        purge(&mut db.conn).expect("Could not purge");
+        assert!(list(&db.conn).expect("Could not list the trash").is_empty());
+        assert!(is_deleted(&db.conn, "a").expect("Could not look up deleted matches"));
+        assert!(restore(&mut db.conn, batch.id).is_err());
+    }
+
+    #[test]
+    fn forgets_deleted_matches_once_restored() {
+        let mut db = database();
+        record_match(&db.conn, "a", 67330);
+        let batch = delete(&mut db.conn, &["a".to_string()]).expect("Could not delete");
+        restore(&mut db.conn, batch.id).expect("Could not restore");
+        assert!(!is_deleted(&db.conn, "a").expect("Could not look up deleted matches"));
+    }
+
+    #[test]
+    fn merges_duplicates_and_restores_them_untouched() {
+        let mut db = database();
+        record_match(&db.conn, "keep", 67330);
+        record_match(&db.conn, "duplicate", 67331);
+        annotations::add_tag(&db.conn, "duplicate", "league").expect("Could not tag");
+        let duplicate_before = rows(&db.conn, "duplicate");
+
+        let batch =
+            merge(&mut db.conn, "keep", &["duplicate".to_string()]).expect("Could not merge");
+        assert_eq!(batch.action, TrashAction::Merge);
+        assert!(rows(&db.conn, "duplicate").is_empty());
+        let drawn = drawn_card_ids(&db.conn, "keep");
+        assert_eq!(drawn, [67330, 67331]);
+
+        restore(&mut db.conn, batch.id).expect("Could not restore");
+        assert_eq!(rows(&db.conn, "duplicate"), duplicate_before);
+        // what the merge copied stays on the kept match
+        assert_eq!(drawn_card_ids(&db.conn, "keep"), [67330, 67331]);
+        assert!(annotations::tags(&db.conn, "keep")
+            .expect("Could not read tags")
+            .contains("league"));
+    }
+
+    #[test]
+    fn refuses_an_empty_delete() {
+        let mut db = database();
+        assert!(delete(&mut db.conn, &[]).is_err());
+    }
+}
@@ -0,0 +1,199 @@
+use chrono::{DateTime, Utc};
+use indoc::indoc;
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::client_log::ClientLogEntry;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Rank {
+    /// `Constructed` or `Limited`
+    pub ranked_format: String,
+    pub season_ordinal: i32,
+    pub class: String,
+    pub level: i32,
+    pub step: i32,
+    pub percentile: Option<f64>,
+    pub leaderboard_place: Option<i32>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum RankEvent {
+    /// Every rank, as the client asks for them on login.
+    Ranks(Vec<Rank>),
+    /// The rank a match just ended in.
+    Updated(Rank),
+    Season {
+        season_ordinal: i32,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    },
+}
+
+fn number(value: &Value, key: &str) -> Option<i32> {
+    value
+        .get(key)
+        .and_then(Value::as_i64)
+        .and_then(|n| i32::try_from(n).ok())
+}
+
+fn timestamp(value: &Value, key: &str) -> Option<DateTime<Utc>> {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .and_then(|timestamp| DateTime::parse_from_rfc3339(timestamp).ok())
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+}
+
+impl Rank {
+    /// `Rank_GetCombinedRankInfo` prefixes every field with `constructed` or `limited`.
+    fn from_combined_rank_info(payload: &Value, prefix: &str, ranked_format: &str) -> Option<Self> {
+        let field = |name: &str| format!("{prefix}{name}");
+        Some(Self {
+            ranked_format: ranked_format.to_string(),
+            season_ordinal: number(payload, &field("SeasonOrdinal"))?,
+            class: payload.get(field("Class"))?.as_str()?.to_string(),
+            level: number(payload, &field("Level"))?,
+            step: number(payload, &field("Step")).unwrap_or_default(),
+            percentile: payload.get(field("Percentile")).and_then(Value::as_f64),
+            leaderboard_place: number(payload, &field("LeaderboardPlace")),
+        })
+    }
+
+    fn from_rank_updated(payload: &Value) -> Option<Self> {
+        Some(Self {
+            ranked_format: payload.get("rankUpdateType")?.as_str()?.to_string(),
+            season_ordinal: number(payload, "seasonOrdinal")?,
+            class: payload.get("newClass")?.as_str()?.to_string(),
+            level: number(payload, "newLevel")?,
+            step: number(payload, "newStep").unwrap_or_default(),
+            percentile: None,
+            leaderboard_place: None,
+        })
+    }
+
+    fn latest(conn: &Connection, ranked_format: &str) -> rusqlite::Result<Option<Self>> {
+        conn.query_row(
+            indoc! {r"
+                SELECT ranked_format, season_ordinal, rank_class, level, step, percentile, leaderboard_place
+                FROM rank_snapshots WHERE ranked_format = ?1
+                ORDER BY id DESC LIMIT 1
+            "},
+            [ranked_format],
+            |row| {
+                Ok(Self {
+                    ranked_format: row.get(0)?,
+                    season_ordinal: row.get(1)?,
+                    class: row.get(2)?,
+                    level: row.get(3)?,
+                    step: row.get(4)?,
+                    percentile: row.get(5)?,
+                    leaderboard_place: row.get(6)?,
+                })
+            },
+        )
+        .optional()
+    }
+
+    /// Whether both are the same rank, leaving out the Mythic percentile and leaderboard place
+    /// that `RankUpdated` doesn't carry.
+    fn same_rank(&self, other: &Self) -> bool {
+        self.ranked_format == other.ranked_format
+            && self.season_ordinal == other.season_ordinal
+            && self.class == other.class
+            && self.level == other.level
+            && self.step == other.step
+    }
+
+    /// Only records a snapshot when the rank moved, the client asks for it on every login. A
+    /// snapshot already recorded at `logged_at`, from reading the same log before, is kept as is.
+    fn write(
+        &self,
+        conn: &Connection,
+        logged_at: DateTime<Utc>,
+        match_id: Option<&str>,
+    ) -> rusqlite::Result<()> {
+        if Self::latest(conn, &self.ranked_format)?.is_some_and(|latest| latest.same_rank(self)) {
+            return Ok(());
+        }
+        conn.execute(
+            indoc! {r"
+                INSERT OR IGNORE INTO rank_snapshots (
+                    created_at, match_id, ranked_format, season_ordinal, rank_class, level, step,
+                    percentile, leaderboard_place
+                )
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            "},
+            (
+                logged_at,
+                match_id,
+                &self.ranked_format,
+                self.season_ordinal,
+                &self.class,
+                self.level,
+                self.step,
+                self.percentile,
+                self.leaderboard_place,
+            ),
+        )?;
+        Ok(())
+    }
+}
+
+impl RankEvent {
+    pub fn from_entry(entry: &ClientLogEntry) -> Option<Self> {
+        let ClientLogEntry::Response { method, payload } = entry else {
+            return None;
+        };
+        match method.as_str() {
+            "Rank_GetCombinedRankInfo" => Some(Self::Ranks(
+                [
+                    Rank::from_combined_rank_info(payload, "constructed", "Constructed"),
+                    Rank::from_combined_rank_info(payload, "limited", "Limited"),
+                ]
+                .into_iter()
+                .flatten()
+                .collect(),
+            )),
+            "RankUpdated" => Rank::from_rank_updated(payload).map(Self::Updated),
+            "Rank_GetSeasonAndRankDetails" => {
+                let season = payload.get("currentSeason")?;
+                Some(Self::Season {
+                    season_ordinal: number(season, "seasonOrdinal")?,
+                    start_time: timestamp(season, "seasonStartTime")?,
+                    end_time: timestamp(season, "seasonEndTime")?,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// `match_id` is the last match played, only an update pushed after it is put down to it.
+    pub fn write(
+        &self,
+        conn: &Connection,
+        logged_at: DateTime<Utc>,
+        match_id: Option<&str>,
+    ) -> rusqlite::Result<()> {
+        match self {
+            Self::Ranks(ranks) => {
+                for rank in ranks {
+                    rank.write(conn, logged_at, None)?;
+                }
+            }
+            Self::Updated(rank) => rank.write(conn, logged_at, match_id)?,
+            Self::Season {
+                season_ordinal,
+                start_time,
+                end_time,
+            } => {
+                conn.execute(
+                    "INSERT OR REPLACE INTO rank_seasons (season_ordinal, start_time, end_time) VALUES (?1, ?2, ?3)",
+                    (season_ordinal, start_time, end_time),
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
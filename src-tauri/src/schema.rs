@@ -0,0 +1,301 @@
+use anyhow::bail;
+use chrono::Utc;
+use indoc::indoc;
+use rusqlite::Connection;
+use tracing::info;
+
+/// Up-migrations for the tables owned by arena-buddy itself, living next to `ap_core`'s tables
+/// in matches.db. Version `n` is `MIGRATIONS[n - 1]`; released migrations are never edited, new
+/// schema changes are appended.
+const MIGRATIONS: &[&str] = &[
+    // Draw counts, drafts, event runs, match formats, ranks, the collection and unknown cards.
+    indoc! {r"
+        CREATE TABLE game_draws (
+            match_id TEXT NOT NULL,
+            game_number INTEGER NOT NULL,
+            card_id INTEGER NOT NULL,
+            quantity INTEGER NOT NULL,
+            PRIMARY KEY (match_id, game_number, card_id)
+        );
+
+        CREATE TABLE drafts (
+            id TEXT PRIMARY KEY,
+            event_name TEXT NOT NULL,
+            created_at DATETIME NOT NULL
+        );
+
+        CREATE TABLE draft_picks (
+            draft_id TEXT NOT NULL REFERENCES drafts (id),
+            pack_number INTEGER NOT NULL,
+            pick_number INTEGER NOT NULL,
+            pack_cards TEXT NOT NULL,
+            picked_card_id INTEGER,
+            PRIMARY KEY (draft_id, pack_number, pick_number)
+        );
+
+        CREATE TABLE event_runs (
+            course_id TEXT PRIMARY KEY,
+            event_name TEXT NOT NULL,
+            current_module TEXT NOT NULL,
+            wins INTEGER NOT NULL,
+            losses INTEGER NOT NULL,
+            card_pool TEXT NOT NULL,
+            main_deck TEXT NOT NULL,
+            sideboard TEXT NOT NULL,
+            prizes TEXT,
+            created_at DATETIME NOT NULL,
+            updated_at DATETIME NOT NULL,
+            entered_at DATETIME
+        );
+
+        CREATE TABLE event_run_matches (
+            course_id TEXT NOT NULL REFERENCES event_runs (course_id),
+            match_id TEXT NOT NULL,
+            PRIMARY KEY (course_id, match_id)
+        );
+
+        CREATE TABLE match_formats (
+            match_id TEXT PRIMARY KEY,
+            event_id TEXT NOT NULL,
+            format TEXT NOT NULL,
+            best_of INTEGER
+        );
+
+        CREATE TABLE rank_snapshots (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            created_at DATETIME NOT NULL,
+            match_id TEXT,
+            ranked_format TEXT NOT NULL,
+            season_ordinal INTEGER NOT NULL,
+            rank_class TEXT NOT NULL,
+            level INTEGER NOT NULL,
+            step INTEGER NOT NULL,
+            percentile REAL,
+            leaderboard_place INTEGER
+        );
+
+        -- Snapshots are stamped with the log line they came from, so replaying a log hits this.
+        CREATE UNIQUE INDEX rank_snapshots_logged ON rank_snapshots (
+            ranked_format, season_ordinal, rank_class, level, step, created_at
+        );
+
+        CREATE TABLE rank_seasons (
+            season_ordinal INTEGER PRIMARY KEY,
+            start_time DATETIME NOT NULL,
+            end_time DATETIME NOT NULL
+        );
+
+        CREATE TABLE collection_snapshots (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            created_at DATETIME NOT NULL,
+            gems INTEGER NOT NULL,
+            gold INTEGER NOT NULL,
+            vault_progress REAL NOT NULL,
+            wildcard_common INTEGER NOT NULL,
+            wildcard_uncommon INTEGER NOT NULL,
+            wildcard_rare INTEGER NOT NULL,
+            wildcard_mythic INTEGER NOT NULL
+        );
+
+        CREATE TABLE collection_cards (
+            snapshot_id INTEGER NOT NULL REFERENCES collection_snapshots (id),
+            card_id INTEGER NOT NULL,
+            quantity INTEGER NOT NULL,
+            PRIMARY KEY (snapshot_id, card_id)
+        );
+
+        CREATE TABLE unknown_cards (
+            card_id INTEGER NOT NULL,
+            match_id TEXT NOT NULL,
+            created_at DATETIME NOT NULL,
+            PRIMARY KEY (card_id, match_id)
+        );
+
+        -- Unknown ids outside of matches: `source` is `draft`, `event_run` or `collection` and
+        -- `source_id` the draft or course they were seen in, empty for the collection.
+        CREATE TABLE unknown_pool_cards (
+            card_id INTEGER NOT NULL,
+            source TEXT NOT NULL,
+            source_id TEXT NOT NULL,
+            created_at DATETIME NOT NULL,
+            PRIMARY KEY (card_id, source, source_id)
+        );
+    "},
+    // Hidden matches and the trash that deleted or merged matches wait in before purging.
+    indoc! {r"
+        CREATE TABLE hidden_matches (
+            match_id TEXT PRIMARY KEY,
+            hidden_at DATETIME NOT NULL
+        );
+
+        CREATE TABLE trash_batches (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            action TEXT NOT NULL,
+            deleted_at DATETIME NOT NULL
+        );
+
+        CREATE TABLE trash_rows (
+            batch_id INTEGER NOT NULL REFERENCES trash_batches (id),
+            match_id TEXT NOT NULL,
+            source_table TEXT NOT NULL,
+            row_data TEXT NOT NULL
+        );
+
+        CREATE INDEX trash_rows_batch_id ON trash_rows (batch_id);
+
+        -- Outlives the trash, so replaying Player.log doesn't record a deleted match again.
+        CREATE TABLE deleted_matches (
+            match_id TEXT PRIMARY KEY,
+            deleted_at DATETIME NOT NULL
+        );
+    "},
+    indoc! {r"
+        CREATE TABLE match_notes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            match_id TEXT NOT NULL,
+            author TEXT,
+            body TEXT NOT NULL,
+            created_at DATETIME NOT NULL,
+            updated_at DATETIME NOT NULL
+        );
+
+        CREATE INDEX match_notes_match_id ON match_notes (match_id);
+
+        CREATE TABLE match_tags (
+            match_id TEXT NOT NULL,
+            tag TEXT NOT NULL,
+            created_at DATETIME NOT NULL,
+            PRIMARY KEY (match_id, tag)
+        );
+    "},
+    // Filled from Rust after migrating since card names live in the cards database.
+    indoc! {r#"
+        CREATE TABLE opponent_cards (
+            match_id TEXT NOT NULL,
+            game_number INTEGER NOT NULL,
+            card_id INTEGER NOT NULL,
+            PRIMARY KEY (match_id, game_number, card_id)
+        );
+
+        -- `match_search` rows are keyed by rowid, which it can look up unlike its columns.
+        CREATE TABLE match_search_ids (
+            search_rowid INTEGER PRIMARY KEY,
+            match_id TEXT NOT NULL UNIQUE
+        );
+
+        CREATE VIRTUAL TABLE match_search USING fts5 (
+            opponent,
+            event,
+            notes,
+            cards,
+            tokenize = "unicode61 remove_diacritics 2"
+        );
+    "#},
+];
+
+fn current_version(conn: &Connection) -> rusqlite::Result<usize> {
+    conn.execute_batch(indoc! {r"
+        CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            applied_at DATETIME NOT NULL
+        );
+    "})?;
+    let version: i64 = conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM schema_version",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(usize::try_from(version).unwrap_or_default())
+}
+
+/// Whether `migrate` has anything to apply.
+pub(crate) fn has_pending_migrations(conn: &Connection) -> rusqlite::Result<bool> {
+    Ok(current_version(conn)? < MIGRATIONS.len())
+}
+
+/// Applies every migration newer than the database, each in its own transaction so a failure
+/// leaves the database at the last version that fully applied.
+pub(crate) fn migrate(conn: &mut Connection) -> anyhow::Result<()> {
+    let current = current_version(conn)?;
+    if current > MIGRATIONS.len() {
+        bail!(
+            "matches.db is at schema version {current}, newer than this build's {}",
+            MIGRATIONS.len()
+        );
+    }
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(current) {
+        let version = index + 1;
+        info!("Applying schema migration {}", version);
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration)?;
+        tx.execute(
+            "INSERT INTO schema_version (version, applied_at) VALUES (?1, ?2)",
+            (i64::try_from(version)?, Utc::now()),
+        )?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table_exists(conn: &Connection, name: &str) -> bool {
+        conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE name = ?1)",
+            [name],
+            |row| row.get(0),
+        )
+        .expect("Could not read sqlite_master")
+    }
+
+    #[test]
+    fn migrates_from_version_zero() {
+        let mut conn = Connection::open_in_memory().expect("Could not open database");
+        assert!(has_pending_migrations(&conn).expect("Could not read version"));
+
+        migrate(&mut conn).expect("Could not migrate");
+        assert_eq!(
+            current_version(&conn).expect("Could not read version"),
+            MIGRATIONS.len()
+        );
+        assert!(!has_pending_migrations(&conn).expect("Could not read version"));
+        for table in [
+            "game_draws",
+            "event_runs",
+            "rank_snapshots",
+            "unknown_cards",
+        ] {
+            assert!(table_exists(&conn, table), "{table} is missing");
+        }
+
+        // a second run has nothing left to apply
+        migrate(&mut conn).expect("Could not migrate again");
+        let versions: i64 = conn
+            .query_row("SELECT COUNT(*) FROM schema_version", [], |row| row.get(0))
+            .expect("Could not count versions");
+        assert_eq!(usize::try_from(versions).ok(), Some(MIGRATIONS.len()));
+    }
+
+    #[test]
+    fn refuses_a_newer_database() {
+        let mut conn = Connection::open_in_memory().expect("Could not open database");
+        current_version(&conn).expect("Could not create schema_version");
+        conn.execute(
+            "INSERT INTO schema_version (version, applied_at) VALUES (?1, ?2)",
+            (
+                i64::try_from(MIGRATIONS.len() + 1).expect("Too many migrations"),
+                Utc::now(),
+            ),
+        )
+        .expect("Could not set version");
+
+        let error = migrate(&mut conn).expect_err("Migrated a newer database");
+        assert!(
+            error.to_string().contains("newer than this build"),
+            "{error}"
+        );
+        assert!(!table_exists(&conn, "game_draws"));
+    }
+}
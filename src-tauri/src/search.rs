@@ -0,0 +1,337 @@
+use std::collections::BTreeSet;
+
+use chrono::{DateTime, Utc};
+use indoc::indoc;
+use itertools::Itertools;
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use crate::database::{Database, DatabaseState, WriteDb};
+use crate::decklist::entry_name;
+
+/// `bm25` weights of the indexed columns: a hit on the opponent or the notes says more about a
+/// match than one of sixty card names.
+const COLUMN_WEIGHTS: &str = "10.0, 2.0, 5.0, 1.0";
+
+const SNIPPET_TOKENS: i32 = 12;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct SearchHit {
+    pub match_id: String,
+    pub opponent_player_name: String,
+    pub event_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// The best matching column's text around the hit, terms wrapped in `<mark>`.
+    pub snippet: String,
+    /// `bm25` score, lower is better.
+    pub rank: f64,
+}
+
+/// The text a match is found by, in `match_search`'s column order.
+#[derive(Debug, Clone, PartialEq)]
+struct IndexedMatch {
+    opponent: String,
+    event: String,
+    notes: String,
+    cards: String,
+}
+
+fn card_names(db: &mut WriteDb<'_>, match_id: &str) -> anyhow::Result<BTreeSet<String>> {
+    let mut card_ids: BTreeSet<i32> = BTreeSet::new();
+    for deck in db.get_decklists(match_id)? {
+        card_ids.extend(deck.quantities().into_keys());
+        card_ids.extend(deck.sideboard_quantities().into_keys());
+    }
+    {
+        let mut statement = db
+            .conn
+            .prepare("SELECT DISTINCT card_id FROM opponent_cards WHERE match_id = ?1")?;
+        let rows = statement.query_map([match_id], |row| row.get(0))?;
+        for card_id in rows {
+            card_ids.insert(card_id?);
+        }
+    }
+    Ok(card_ids
+        .iter()
+        .filter_map(|card_id| db.cards.get(*card_id))
+        .map(|entry| entry_name(entry).to_string())
+        .collect())
+}
+
+/// What the index should hold for `match_id`, `None` once the match is gone.
+fn indexed_match(db: &mut WriteDb<'_>, match_id: &str) -> anyhow::Result<Option<IndexedMatch>> {
+    let indexed = db
+        .conn
+        .query_row(
+            indoc! {r"
+                SELECT
+                    m.opponent_player_name,
+                    mf.event_id,
+                    mf.format,
+                    (SELECT GROUP_CONCAT(body, char(10)) FROM match_notes WHERE match_id = m.id)
+                FROM matches m LEFT JOIN match_formats mf ON m.id = mf.match_id
+                WHERE m.id = ?1
+            "},
+            [match_id],
+            |row| {
+                let event_id: Option<String> = row.get(1)?;
+                let format: Option<String> = row.get(2)?;
+                let notes: Option<String> = row.get(3)?;
+                Ok(IndexedMatch {
+                    opponent: row.get(0)?,
+                    event: [event_id, format].into_iter().flatten().join(" "),
+                    notes: notes.unwrap_or_default(),
+                    cards: String::new(),
+                })
+            },
+        )
+        .optional()?;
+    let Some(mut indexed) = indexed else {
+        return Ok(None);
+    };
+    indexed.cards = card_names(db, match_id)?.into_iter().join("\n");
+    Ok(Some(indexed))
+}
+
+/// Brings the index row of `match_id` in line with the database, returning whether it changed.
+/// Rows are keyed by their `match_search_ids` rowid so replacing one doesn't scan the index.
+fn update_match(db: &mut WriteDb<'_>, match_id: &str) -> anyhow::Result<bool> {
+    let indexed = indexed_match(db, match_id)?;
+    let tx = db.conn.transaction()?;
+    let search_rowid: Option<i64> = tx
+        .query_row(
+            "SELECT search_rowid FROM match_search_ids WHERE match_id = ?1",
+            [match_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if let Some(search_rowid) = search_rowid {
+        let current = tx
+            .query_row(
+                "SELECT opponent, event, notes, cards FROM match_search WHERE rowid = ?1",
+                [search_rowid],
+                |row| {
+                    Ok(IndexedMatch {
+                        opponent: row.get(0)?,
+                        event: row.get(1)?,
+                        notes: row.get(2)?,
+                        cards: row.get(3)?,
+                    })
+                },
+            )
+            .optional()?;
+        if current.is_some() && current == indexed {
+            return Ok(false);
+        }
+        tx.execute("DELETE FROM match_search WHERE rowid = ?1", [search_rowid])?;
+    }
+    match indexed {
+        Some(indexed) => {
+            tx.execute(
+                "INSERT OR IGNORE INTO match_search_ids (match_id) VALUES (?1)",
+                [match_id],
+            )?;
+            tx.execute(
+                indoc! {r"
+                    INSERT INTO match_search (rowid, opponent, event, notes, cards)
+                    SELECT search_rowid, ?2, ?3, ?4, ?5 FROM match_search_ids WHERE match_id = ?1
+                "},
+                (
+                    match_id,
+                    indexed.opponent,
+                    indexed.event,
+                    indexed.notes,
+                    indexed.cards,
+                ),
+            )?;
+        }
+        None => {
+            tx.execute(
+                "DELETE FROM match_search_ids WHERE match_id = ?1",
+                [match_id],
+            )?;
+        }
+    }
+    tx.commit()?;
+    Ok(true)
+}
+
+/// Replaces the index row of `match_id` with what the database holds now, dropping it if the
+/// match is gone.
+pub(crate) fn index_match(db: &mut WriteDb<'_>, match_id: &str) -> anyhow::Result<()> {
+    update_match(db, match_id)?;
+    Ok(())
+}
+
+/// Indexes every match the index doesn't know yet, returning how many.
+pub(crate) fn index_missing(db: &mut WriteDb<'_>) -> anyhow::Result<usize> {
+    let match_ids: Vec<String> = {
+        let mut statement = db.conn.prepare(
+            "SELECT id FROM matches WHERE id NOT IN (SELECT match_id FROM match_search_ids)",
+        )?;
+        let rows = statement.query_map([], |row| row.get(0))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+    for match_id in &match_ids {
+        index_match(db, match_id)?;
+    }
+    Ok(match_ids.len())
+}
+
+/// Re-indexes every match, for when card names changed under it.
+pub(crate) fn rebuild(db: &mut WriteDb<'_>) -> anyhow::Result<usize> {
+    db.conn
+        .execute_batch("DELETE FROM match_search; DELETE FROM match_search_ids;")?;
+    index_missing(db)
+}
+
+/// Checks every match against the index, returning how many rows it fixed. Catches matches
+/// recorded before the index existed, restored from a backup, or whose last write was never
+/// indexed because indexing failed or the app stopped in between. Matches are taken one at a
+/// time so ingest isn't held up.
+fn refresh(db: &Database) -> anyhow::Result<usize> {
+    let match_ids: Vec<String> = {
+        let db = db.write();
+        let mut statement = db.conn.prepare(indoc! {r"
+            SELECT id FROM matches
+            UNION
+            SELECT match_id FROM match_search_ids
+        "})?;
+        let rows = statement.query_map([], |row| row.get(0))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+    let mut updated = 0;
+    for match_id in &match_ids {
+        if update_match(&mut db.write(), match_id)? {
+            updated += 1;
+        }
+    }
+    Ok(updated)
+}
+
+pub(crate) fn start_indexing(db: DatabaseState) {
+    std::thread::spawn(move || match refresh(&db) {
+        Ok(0) => {}
+        Ok(updated) => info!("Updated {} matches in the search index", updated),
+        Err(e) => error!("Error indexing matches for search: {}", e),
+    });
+}
+
+/// Turns what was typed into the search box into an FTS5 query: every word has to prefix-match
+/// somewhere, FTS5's own operators are taken literally.
+fn fts_query(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split_whitespace()
+        .map(|term| format!("\"{}\"*", term.replace('"', "\"\"")))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// Matches for `text`, best first.
+pub(crate) fn search(
+    conn: &Connection,
+    text: &str,
+    limit: u32,
+) -> rusqlite::Result<Vec<SearchHit>> {
+    let Some(query) = fts_query(text) else {
+        return Ok(Vec::new());
+    };
+    let mut statement = conn.prepare(&format!(
+        indoc! {r"
+            SELECT
+                ids.match_id,
+                m.opponent_player_name,
+                mf.event_id,
+                m.created_at,
+                snippet(match_search, -1, '<mark>', '</mark>', '…', {snippet_tokens}),
+                bm25(match_search, {weights}) AS score
+            FROM match_search
+            JOIN match_search_ids ids ON ids.search_rowid = match_search.rowid
+            JOIN matches m ON m.id = ids.match_id
+            LEFT JOIN match_formats mf ON mf.match_id = ids.match_id
+            WHERE match_search MATCH ?1
+            ORDER BY score
+            LIMIT ?2
+        "},
+        snippet_tokens = SNIPPET_TOKENS,
+        weights = COLUMN_WEIGHTS,
+    ))?;
+    let hits = statement.query_map((query, limit), |row| {
+        Ok(SearchHit {
+            match_id: row.get(0)?,
+            opponent_player_name: row.get(1)?,
+            event_id: row.get(2)?,
+            created_at: row.get(3)?,
+            snippet: row.get(4)?,
+            rank: row.get(5)?,
+        })
+    })?;
+    hits.collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::testing::{database, insert_match};
+
+    #[test]
+    fn quotes_every_term_as_a_prefix() {
+        assert_eq!(
+            fts_query("  Lightning   bolt ").as_deref(),
+            Some(r#""Lightning"* "bolt"*"#)
+        );
+    }
+
+    #[test]
+    fn takes_fts_syntax_literally() {
+        assert_eq!(
+            fts_query(r#"NOT say "hi" opponent:*"#).as_deref(),
+            Some(r#""NOT"* "say"* """hi"""* "opponent:*"*"#)
+        );
+    }
+
+    #[test]
+    fn skips_blank_queries() {
+        assert_eq!(fts_query(""), None);
+        assert_eq!(fts_query(" \t\n"), None);
+    }
+
+    #[test]
+    fn finds_matches_by_any_column() {
+        let db = database();
+        for (match_id, opponent, notes) in [
+            ("a", "Karn", "kept a greedy hand"),
+            ("b", "Jace", "NOT a \"good\" matchup"),
+        ] {
+            insert_match(&db.conn, match_id, opponent);
+            db.conn
+                .execute(
+                    "INSERT INTO match_search_ids (match_id) VALUES (?1)",
+                    [match_id],
+                )
+                .expect("Could not insert index id");
+            db.conn
+                .execute(
+                    indoc! {r"
+                        INSERT INTO match_search (rowid, opponent, event, notes, cards)
+                        SELECT search_rowid, ?2, '', ?3, '' FROM match_search_ids WHERE match_id = ?1
+                    "},
+                    (match_id, opponent, notes),
+                )
+                .expect("Could not index match");
+        }
+
+        let match_ids = |text: &str| -> Vec<String> {
+            search(&db.conn, text, 10)
+                .expect("Could not search")
+                .into_iter()
+                .map(|hit| hit.match_id)
+                .collect()
+        };
+        assert_eq!(match_ids("kar"), ["a"]);
+        assert_eq!(match_ids("greed hand"), ["a"]);
+        assert_eq!(match_ids("not \"good\""), ["b"]);
+        assert!(match_ids("greedy jace").is_empty());
+    }
+}
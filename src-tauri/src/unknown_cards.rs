@@ -0,0 +1,212 @@
+use std::collections::BTreeSet;
+
+use chrono::Utc;
+use indoc::indoc;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::cards::Cards;
+use crate::database::Database;
+
+/// Card ids in the decklists, mulligans and opponent cards of every match, or only of `?1` when
+/// it isn't null. `ap_core` stores deck lists as JSON arrays and hands as comma separated ids.
+const MATCH_CARD_IDS: &str = indoc! {r"
+    SELECT d.match_id, j.value FROM decklists d, json_each(d.deck_cards) j
+    WHERE ?1 IS NULL OR d.match_id = ?1
+    UNION
+    SELECT d.match_id, j.value FROM decklists d, json_each(d.sideboard_cards) j
+    WHERE ?1 IS NULL OR d.match_id = ?1
+    UNION
+    SELECT m.match_id, j.value FROM mulligans m, json_each('[' || m.hand || ']') j
+    WHERE ?1 IS NULL OR m.match_id = ?1
+    UNION
+    SELECT match_id, card_id FROM opponent_cards
+    WHERE ?1 IS NULL OR match_id = ?1
+"};
+
+/// Card ids in drafts, event runs and the latest collection snapshot, which owns every card the
+/// earlier ones did. Packs and pools are stored as comma separated ids.
+const POOL_CARD_IDS: &str = indoc! {r"
+    SELECT 'draft', p.draft_id, j.value FROM draft_picks p, json_each('[' || p.pack_cards || ']') j
+    UNION
+    SELECT 'draft', draft_id, picked_card_id FROM draft_picks WHERE picked_card_id IS NOT NULL
+    UNION
+    SELECT 'event_run', r.course_id, j.value FROM event_runs r, json_each('[' || r.card_pool || ']') j
+    UNION
+    SELECT 'event_run', r.course_id, j.value FROM event_runs r, json_each('[' || r.main_deck || ']') j
+    UNION
+    SELECT 'event_run', r.course_id, j.value FROM event_runs r, json_each('[' || r.sideboard || ']') j
+    UNION
+    SELECT 'collection', '', card_id FROM collection_cards
+    WHERE snapshot_id = (SELECT MAX(id) FROM collection_snapshots)
+"};
+
+/// `(match_id, card_id)` of the ids in `match_id`, or in every match, the cards don't know.
+fn unknown_match_card_ids(
+    conn: &Connection,
+    cards: &Cards,
+    match_id: Option<&str>,
+) -> rusqlite::Result<BTreeSet<(String, i32)>> {
+    let mut statement = conn.prepare(MATCH_CARD_IDS)?;
+    let rows = statement.query_map([match_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    let mut unknown = BTreeSet::new();
+    for row in rows {
+        let (match_id, card_id): (String, i32) = row?;
+        if cards.get(card_id).is_none() {
+            unknown.insert((match_id, card_id));
+        }
+    }
+    Ok(unknown)
+}
+
+/// `(source, source_id, card_id)` of the ids in drafts, event runs and the collection the cards
+/// don't know.
+fn unknown_pool_card_ids(
+    conn: &Connection,
+    cards: &Cards,
+) -> rusqlite::Result<BTreeSet<(String, String, i32)>> {
+    let mut statement = conn.prepare(POOL_CARD_IDS)?;
+    let rows = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+    let mut unknown = BTreeSet::new();
+    for row in rows {
+        let (source, source_id, card_id): (String, String, i32) = row?;
+        if cards.get(card_id).is_none() {
+            unknown.insert((source, source_id, card_id));
+        }
+    }
+    Ok(unknown)
+}
+
+fn insert_match_card_ids(
+    conn: &Connection,
+    unknown: &BTreeSet<(String, i32)>,
+) -> rusqlite::Result<()> {
+    let mut statement = conn.prepare(
+        "INSERT OR IGNORE INTO unknown_cards (card_id, match_id, created_at) VALUES (?1, ?2, ?3)",
+    )?;
+    let now = Utc::now();
+    for (match_id, card_id) in unknown {
+        statement.execute((card_id, match_id, now))?;
+    }
+    Ok(())
+}
+
+fn insert_pool_card_ids(
+    conn: &Connection,
+    unknown: &BTreeSet<(String, String, i32)>,
+) -> rusqlite::Result<()> {
+    let mut statement = conn.prepare(indoc! {r"
+        INSERT OR IGNORE INTO unknown_pool_cards (card_id, source, source_id, created_at)
+        VALUES (?1, ?2, ?3, ?4)
+    "})?;
+    let now = Utc::now();
+    for (source, source_id, card_id) in unknown {
+        statement.execute((card_id, source, source_id, now))?;
+    }
+    Ok(())
+}
+
+/// Remembers the ids of a freshly written match that the cards database can't resolve.
+pub(crate) fn record_match(conn: &Connection, cards: &Cards, match_id: &str) -> anyhow::Result<()> {
+    let unknown = unknown_match_card_ids(conn, cards, Some(match_id))?;
+    insert_match_card_ids(conn, &unknown)?;
+    Ok(())
+}
+
+/// Remembers the ids of drafts, event runs and the collection that the cards database can't
+/// resolve, after any of them was written.
+pub(crate) fn record_pools(conn: &Connection, cards: &Cards) -> anyhow::Result<()> {
+    let unknown = unknown_pool_card_ids(conn, cards)?;
+    insert_pool_card_ids(conn, &unknown)?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct ResolveReport {
+    pub resolved_card_ids: Vec<i32>,
+    /// Matches whose decklists, mulligans and opponent cards now resolve every card.
+    pub completed_match_ids: Vec<String>,
+    pub still_unknown: usize,
+}
+
+/// Re-checks everything stored against the current cards database, recording ids we hadn't
+/// seen yet and dropping the ones that resolve now. Ids are collected with a reader so ingest
+/// only waits for the writer while the differences are applied.
+pub(crate) fn reconcile(db: &Database) -> anyhow::Result<ResolveReport> {
+    let started_at = Utc::now();
+    let (match_card_ids, pool_card_ids) = {
+        let db = db.read()?;
+        (
+            unknown_match_card_ids(&db.conn, &db.cards, None)?,
+            unknown_pool_card_ids(&db.conn, &db.cards)?,
+        )
+    };
+
+    let mut db = db.write();
+    let tx = db.conn.transaction()?;
+    // rows ingest recorded after the ids were collected aren't known to be resolved yet
+    let previous_match_card_ids: BTreeSet<(String, i32)> = {
+        let mut statement =
+            tx.prepare("SELECT match_id, card_id FROM unknown_cards WHERE created_at < ?1")?;
+        let rows = statement.query_map([started_at], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+    let previous_pool_card_ids: BTreeSet<(String, String, i32)> = {
+        let mut statement = tx.prepare(
+            "SELECT source, source_id, card_id FROM unknown_pool_cards WHERE created_at < ?1",
+        )?;
+        let rows = statement.query_map([started_at], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+    insert_match_card_ids(&tx, &match_card_ids)?;
+    insert_pool_card_ids(&tx, &pool_card_ids)?;
+    {
+        let mut statement =
+            tx.prepare("DELETE FROM unknown_cards WHERE match_id = ?1 AND card_id = ?2")?;
+        for (match_id, card_id) in previous_match_card_ids.difference(&match_card_ids) {
+            statement.execute((match_id, card_id))?;
+        }
+        let mut statement = tx.prepare(
+            "DELETE FROM unknown_pool_cards WHERE source = ?1 AND source_id = ?2 AND card_id = ?3",
+        )?;
+        for (source, source_id, card_id) in previous_pool_card_ids.difference(&pool_card_ids) {
+            statement.execute((source, source_id, card_id))?;
+        }
+    }
+    tx.commit()?;
+
+    let still_unknown_ids: BTreeSet<i32> = match_card_ids
+        .iter()
+        .map(|(_, card_id)| *card_id)
+        .chain(pool_card_ids.iter().map(|(_, _, card_id)| *card_id))
+        .collect();
+    let incomplete_matches: BTreeSet<&String> = match_card_ids
+        .iter()
+        .map(|(match_id, _)| match_id)
+        .collect();
+    Ok(ResolveReport {
+        resolved_card_ids: previous_match_card_ids
+            .iter()
+            .map(|(_, card_id)| *card_id)
+            .chain(
+                previous_pool_card_ids
+                    .iter()
+                    .map(|(_, _, card_id)| *card_id),
+            )
+            .filter(|card_id| !still_unknown_ids.contains(card_id))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect(),
+        completed_match_ids: previous_match_card_ids
+            .iter()
+            .map(|(match_id, _)| match_id)
+            .filter(|match_id| !incomplete_matches.contains(match_id))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .cloned()
+            .collect(),
+        still_unknown: still_unknown_ids.len(),
+    })
+}
@@ -0,0 +1,24 @@
+"use client";
+
+// Mirrors `CommandError` in src-tauri/src/commands/error.rs.
+export interface CommandError {
+  kind: "not_found" | "database" | "lock" | "card_database" | "task";
+  message: string;
+}
+
+const TITLES: Record<CommandError["kind"], string> = {
+  not_found: "Not found",
+  database: "Database error",
+  lock: "Busy, try again",
+  card_database: "Card database error",
+  task: "Something went wrong",
+};
+
+export default function CommandErrorMessage({ error }: { error: CommandError }) {
+  return (
+    <div className="text-red-500">
+      <p className="font-bold">{TITLES[error.kind] ?? "Error"}</p>
+      <p>{error.message}</p>
+    </div>
+  );
+}
@@ -1,7 +1,8 @@
 "use client";
 
-import { invoke } from "@tauri-apps/api/core";
+import { convertFileSrc, invoke } from "@tauri-apps/api/core";
 import React, { useEffect, useState } from "react";
+import CommandErrorMessage, { CommandError } from "../command-error";
 
 interface DeckList {
   game_number: number;
@@ -111,7 +112,7 @@ const CardEntry: React.FC<CardEntryProps> = ({ identifier, key, card, includeMan
              const y = (rect ? rect.top: 0) + 10;
              img.style.zIndex = '1000';
              img.style.position = 'fixed';
-             img.src = card.image_uri;
+             img.src = convertFileSrc(card.image_uri, "cardimg");
              img.className = `absolute h-auto w-auto0`;
              img.style.top = `${y}px`;
              img.style.left = `${x}px`;
@@ -135,6 +136,7 @@ const CardEntry: React.FC<CardEntryProps> = ({ identifier, key, card, includeMan
 
 export default function MatchDetails() {
   const [match, setMatch] = useState<MatchDetails | null>(null);
+  const [error, setError] = useState<CommandError | null>(null);
   useEffect(() => {
     let params = new URLSearchParams(document.location.search);
     let id = params.get("id");
@@ -144,7 +146,10 @@ export default function MatchDetails() {
           console.log(result);
           setMatch(result);
         })
-        .catch(console.error);
+        .catch((e: CommandError) => {
+          console.error(e);
+          setError(e);
+        });
     } else {
       setMatch({
         id: 0,
@@ -194,6 +199,7 @@ export default function MatchDetails() {
 
   return (
     <div className="container mx-auto px-4">
+      {error && <CommandErrorMessage error={error} />}
       {match && (
         <div>
           <div>
@@ -3,6 +3,7 @@
 import { invoke } from "@tauri-apps/api/core";
 import { useState, useEffect } from "react";
 import Link from "next/link";
+import CommandErrorMessage, { CommandError } from "../command-error";
 
 interface MTGAMatch {
   id: number;
@@ -24,15 +25,23 @@ function formatDate(dateString: string): string {
 
 export default function MatchesTable() {
   const [matches, setMatches] = useState<MTGAMatch[]>([]);
+  const [error, setError] = useState<CommandError | null>(null);
 
   useEffect(() => {
     invoke<MTGAMatch[]>("command_matches", {})
       .then((result) => {
         setMatches(result);
       })
-      .catch(console.error);
+      .catch((e: CommandError) => {
+        console.error(e);
+        setError(e);
+      });
   }, []);
 
+  if (error) {
+    return <CommandErrorMessage error={error} />;
+  }
+
   return (
     <table>
       <thead>
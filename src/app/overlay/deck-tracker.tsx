@@ -0,0 +1,78 @@
+"use client";
+
+import { listen } from "@tauri-apps/api/event";
+import { useEffect, useState } from "react";
+
+interface Card {
+  name: string;
+  quantity: number;
+  mana_value: number;
+  image_uri: string;
+}
+
+interface LibraryCard {
+  card: Card;
+  remaining: number;
+  draw_odds: number;
+}
+
+interface DeckTracker {
+  match_id: string;
+  game_number: number;
+  library_size: number;
+  library: LibraryCard[];
+  sideboard: Card[];
+  opponent_cards: Card[];
+}
+
+export default function DeckTrackerOverlay() {
+  const [tracker, setTracker] = useState<DeckTracker | null>(null);
+
+  useEffect(() => {
+    const unlisten = listen<DeckTracker>("deck-tracker-updated", (event) => {
+      setTracker(event.payload);
+    });
+    return () => {
+      unlisten.then((f) => f());
+    };
+  }, []);
+
+  if (tracker === null) {
+    return <p className="p-2">Waiting for a game to start...</p>;
+  }
+
+  return (
+    <div className="p-2 text-sm">
+      <h2 className="font-bold">
+        Game {tracker.game_number} - {tracker.library_size} cards in library
+      </h2>
+      <table className="w-full">
+        <tbody>
+          {tracker.library
+            .filter((entry) => entry.remaining > 0)
+            .map((entry, index) => (
+              <tr key={index}>
+                <td>{entry.remaining}</td>
+                <td>{entry.card.name}</td>
+                <td className="text-right">{(entry.draw_odds * 100).toFixed(1)}%</td>
+              </tr>
+            ))}
+        </tbody>
+      </table>
+      {tracker.sideboard.length > 0 && (
+        <>
+          <h2 className="font-bold mt-2">Sideboard</h2>
+          {tracker.sideboard.map((card, index) => (
+            <p key={index}>
+              {card.quantity} {card.name}
+            </p>
+          ))}
+        </>
+      )}
+      <h2 className="font-bold mt-2">Opponent cards seen</h2>
+      {tracker.opponent_cards.map((card, index) => (
+        <p key={index}>{card.name}</p>
+      ))}
+    </div>
+  );
+}
@@ -0,0 +1,9 @@
+import DeckTrackerOverlay from "@/app/overlay/deck-tracker";
+
+export default function Overlay() {
+    return (
+        <main className="">
+            <DeckTrackerOverlay />
+        </main>
+    );
+}